@@ -85,6 +85,10 @@ impl input::EventMapper<glutin::Event> for GlutinEventMapper {
 				X -> X,
 				Y -> Y,
 				Z -> Z,
+				Equals -> Plus,
+				Minus -> Minus,
+				Add -> KpPlus,
+				Subtract -> KpMinus,
 				Escape -> Esc
 			]
 		}
@@ -85,16 +85,30 @@ pub fn main_loop(minion_gene_pool: &str) {
 
 		let r = update_result;
 		// draw some debug text on screen
-		renderer.draw_text(&format!("F: {} E: {:.3} FT: {:.2} SFT: {:.2} FPS: {:.1} P: {} E: {}",
+		renderer.draw_text(&format!("F: {} E: {:.3} T: {:.1} ({}) FT: {:.2} SFT: {:.2} FPS: {:.1} P: {} G: {:?} E: {}",
 		                            r.frame_count,
 		                            r.frame_elapsed,
+		                            r.simulated_time,
+		                            r.time_scale,
 		                            r.frame_time * 1000.0,
 		                            r.frame_time_smooth * 1000.0,
 		                            r.fps,
 		                            r.population,
+		                            r.genders,
 		                            r.extinctions),
 		                   [10, 10],
 		                   [1.0; 4]);
+		let l = &r.ledger;
+		renderer.draw_text(&format!("IN: {:.2}/s MET: {:.2}/s W: {:.2}/s CR: {:.2}/s D: {:.2}/s S: {:.1} B: {:.2}/s",
+		                            l.inflow,
+		                            l.metabolic,
+		                            l.waste,
+		                            l.created,
+		                            l.decayed,
+		                            l.total_stored(),
+		                            l.balance()),
+		                   [10, 30],
+		                   [1.0; 4]);
 
 		// push the commands
 		renderer.end_frame(&mut device);
@@ -10,12 +10,18 @@ use core::math::Relative;
 use core::math::Smooth;
 
 use core::resource::ResourceLoader;
+use core::config::Config;
+use std::collections::HashMap;
+use std::f32::consts;
+use std::fmt;
 
 use backend::obj;
 use backend::obj::*;
 use backend::world;
 use backend::world::segment;
 use backend::world::agent;
+use backend::world::gender;
+use backend::world::obstacle;
 use backend::systems;
 use backend::systems::System;
 
@@ -42,6 +48,16 @@ pub enum Event {
 	Reload,
 	DumpToFile,
 	ToggleDebug,
+	ToggleNavigation,
+	TogglePredation,
+	NextMatingSystem,
+	ToggleDormancy,
+	NextRecovery,
+	ToggleDrag,
+	ToggleFlow,
+	ToggleTopology,
+	NextTimeScale,
+	PrevTimeScale,
 
 	AppQuit,
 
@@ -94,6 +110,7 @@ pub struct Systems {
 	ai: systems::AiSystem,
 	alife: systems::AlifeSystem,
 	audio: systems::AudioSystem,
+	scent: systems::ScentSystem,
 }
 
 impl Systems {
@@ -101,6 +118,7 @@ impl Systems {
 		vec![&mut self.animation as &mut systems::System,
 		     &mut self.audio as &mut systems::System,
 		     &mut self.game as &mut systems::System,
+		     &mut self.scent as &mut systems::System,
 		     &mut self.ai as &mut systems::System,
 		     &mut self.alife as &mut systems::System,
 		     &mut self.physics as &mut systems::System]
@@ -125,6 +143,43 @@ impl Systems {
 	}
 }
 
+// The backend advances in fixed steps, however long the frames take
+#[derive(Clone)]
+pub struct Timestep {
+	pub step: f32,
+	// past this many steps in a frame the simulation falls behind rather than grinding to a halt
+	pub max_substeps: usize,
+}
+
+impl Default for Timestep {
+	fn default() -> Self {
+		Timestep {
+			step: 1. / 60.,
+			max_substeps: 5,
+		}
+	}
+}
+
+// How much simulated time goes by for every second on the clock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeScale {
+	Times(f32),
+	// as many steps as fit in the frame budget
+	Max,
+}
+
+impl fmt::Display for TimeScale {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			TimeScale::Times(k) => write!(f, "{}x", k),
+			TimeScale::Max => write!(f, "max"),
+		}
+	}
+}
+
+// wall clock time spent stepping each frame at maximum speed
+const MAX_SPEED_BUDGET: f32 = 1. / 30.;
+
 bitflags! {
 	pub flags DebugFlags: u32 {
 		const DEBUG_TARGETS = 0x1,
@@ -140,11 +195,21 @@ pub struct App {
 	frame_start: SystemStopwatch,
 	frame_elapsed: f32,
 	frame_smooth: math::MovingAverage<f32>,
+	timestep: Timestep,
+	time_scales: Cycle<TimeScale>,
+	// time not yet simulated, less than a step after each frame
+	accumulator: f32,
+	// segment transforms before the last step, to draw in between
+	previous: HashMap<agent::Key, Transform>,
+	// energy flows averaged over the last second of simulated time
+	ledger_rate: world::ledger::Rate,
 	is_running: bool,
 	//
 	camera: math::Inertial<f32>,
 	lights: Cycle<Rgba>,
 	backgrounds: Cycle<Rgba>,
+	mating_systems: Cycle<gender::MatingSystem>,
+	recoveries: Cycle<world::Recovery>,
 	//
 	world: world::World,
 	systems: Systems,
@@ -166,19 +231,29 @@ pub struct Update {
 	pub frame_time_smooth: f32,
 	pub fps: f32,
 	pub population: usize,
+	pub genders: Box<[usize]>,
 	pub extinctions: usize,
+	// flows per second, stock as of the last tick
+	pub ledger: world::ledger::Ledger,
+	pub time_scale: TimeScale,
+	pub simulated_time: f32,
 }
 
 impl App {
 	pub fn new<R>(w: u32, h: u32, scale: f32, resource_loader: &R, minion_gene_pool: &str) -> Self
 		where R: ResourceLoader<u8> {
-		App {
+		let config = resource_loader.load("config.ini")
+			.map(|data| Config::parse_from_resource(&data))
+			.unwrap_or_else(|_| Config::default());
+		let mut app = App {
 			viewport: Viewport::rect(w, h, scale),
 			input_state: input::InputState::default(),
 
 			camera: Self::init_camera(),
 			lights: Self::init_lights(),
 			backgrounds: Self::init_backgrounds(),
+			mating_systems: Self::init_mating_systems(),
+			recoveries: Self::init_recoveries(),
 
 			world: world::World::new(resource_loader, minion_gene_pool),
 			// subsystems
@@ -189,10 +264,95 @@ impl App {
 			frame_start: SystemStopwatch::new(),
 			wall_clock_start: SystemStopwatch::new(),
 			frame_smooth: math::MovingAverage::new(120),
+			timestep: Timestep::default(),
+			time_scales: Self::init_time_scales(),
+			accumulator: 0.,
+			previous: HashMap::new(),
+			ledger_rate: world::ledger::Rate::new(1.),
 			is_running: true,
 			// debug
 			debug_flags: DebugFlags::empty(),
+		};
+		app.configure(&config);
+		app
+	}
+
+	// settings from the config file, anything missing keeps its default
+	fn configure(&mut self, config: &Config) {
+		for t in agent::AgentType::all() {
+			if let Some(capacity) = config.get::<world::swarm::Capacity>(&format!("capacity.{}", t)) {
+				info!("capacity: {} {:?}", t, capacity);
+				self.world.set_capacity(t, capacity);
+			}
+		}
+		if let Some(count) = config.get::<u8>("genders.count") {
+			// without a matrix, any two different genders can mate
+			let genders = match config.get_list::<bool>("genders.compatibility") {
+				Some(compatibility) => gender::Genders::new(count, &compatibility),
+				None => gender::Genders::from_system(gender::MatingSystem::MatingTypes(count)),
+			};
+			info!("genders: {:?}", genders);
+			self.world.set_genders(genders);
+		}
+		if let Some(recovery) = config.get::<world::Recovery>("recovery") {
+			info!("extinction recovery: {:?}", recovery);
+			self.world.set_recovery(recovery);
+		}
+		let mut criteria = self.world.hall_of_fame().criteria().clone();
+		criteria.lifespan = config.get_or("hall_of_fame.lifespan", criteria.lifespan);
+		criteria.offspring = config.get_or("hall_of_fame.offspring", criteria.offspring);
+		criteria.energy = config.get_or("hall_of_fame.energy", criteria.energy);
+		self.world.hall_of_fame_mut().set_criteria(criteria);
+		let mut metabolism = systems::alife::Metabolism::default();
+		metabolism.basal = config.get_or("metabolism.basal", metabolism.basal);
+		metabolism.actuation = config.get_or("metabolism.actuation", metabolism.actuation);
+		metabolism.brain = config.get_or("metabolism.brain", metabolism.brain);
+		metabolism.motor = config.get_or("metabolism.motor", metabolism.motor);
+		self.systems.alife.set_metabolism(metabolism);
+		let mut drag = systems::physics::Drag::default();
+		drag.enabled = config.get_or("drag.enabled", drag.enabled);
+		drag.normal = config.get_or("drag.normal", drag.normal);
+		drag.tangential = config.get_or("drag.tangential", drag.tangential);
+		self.systems.physics.set_drag(drag);
+		{
+			let flow = self.world.flow_mut();
+			flow.enabled = config.get_or("flow.enabled", flow.enabled);
+			if let Some(uniform) = config.get_list::<f32>("flow.uniform") {
+				if uniform.len() == 2 {
+					flow.uniform = Velocity::new(uniform[0], uniform[1]);
+				} else {
+					warn!("config: flow.uniform needs x and y, got {:?}", uniform);
+				}
+			}
+			flow.turbulence.amplitude = config.get_or("flow.turbulence.amplitude", flow.turbulence.amplitude);
+			flow.turbulence.scale = config.get_or("flow.turbulence.scale", flow.turbulence.scale);
+			flow.turbulence.rate = config.get_or("flow.turbulence.rate", flow.turbulence.rate);
+		}
+		self.world.stir(config.get_or("flow.vortex.strength", world::VORTEX_STRENGTH),
+		                config.get_or("flow.vortex.radius", world::VORTEX_RADIUS));
+		let mut collisions = systems::physics::Collisions::default();
+		for &a in agent::AgentType::all() {
+			for &b in agent::AgentType::all() {
+				if let Some(collide) = config.get::<bool>(&format!("collide.{}.{}", a, b)) {
+					info!("collisions: {} with {}: {}", a, b, collide);
+					collisions = collisions.with(a, b, collide);
+				}
+			}
+		}
+		self.systems.physics.set_collisions(collisions);
+		let mut iterations = systems::physics::Iterations::default();
+		iterations.velocity = config.get_or("physics.velocity_iterations", iterations.velocity);
+		iterations.position = config.get_or("physics.position_iterations", iterations.position);
+		self.systems.physics.set_iterations(iterations);
+		let mut timestep = Timestep::default();
+		timestep.step = config.get_or("timestep.step", timestep.step);
+		timestep.max_substeps = config.get_or("timestep.max_substeps", timestep.max_substeps);
+		if timestep.step > 0. {
+			self.set_timestep(timestep);
+		} else {
+			warn!("timestep: {} is not a step forward, keeping {}", timestep.step, self.timestep.step);
 		}
+		self.world.scatter_obstacles(config.get_or("obstacles", world::N_OBSTACLES));
 	}
 
 	fn init_camera() -> math::Inertial<f32> {
@@ -221,8 +381,54 @@ impl App {
 		             [0.01, 0.01, 0.01, 1.0]])
 	}
 
+	fn init_mating_systems() -> Cycle<gender::MatingSystem> {
+		Cycle::new(&[gender::MatingSystem::MatingTypes(gender::MAX_GENDERS),
+		             gender::MatingSystem::Hermaphrodite,
+		             gender::MatingSystem::TwoSexes,
+		             gender::MatingSystem::MatingTypes(3)])
+	}
+
+	fn next_mating_system(&mut self) {
+		let mating_system = self.mating_systems.next();
+		info!("mating system: {:?}", mating_system);
+		self.world.set_genders(gender::Genders::from_system(mating_system));
+	}
+
+	fn init_time_scales() -> Cycle<TimeScale> {
+		Cycle::new(&[TimeScale::Times(1.), TimeScale::Times(2.), TimeScale::Times(10.), TimeScale::Max])
+	}
+
+	fn init_recoveries() -> Cycle<world::Recovery> {
+		Cycle::new(&[world::Recovery::GenePool,
+		             world::Recovery::LastDead(20),
+		             world::Recovery::HallOfFame,
+		             world::Recovery::Random,
+		             world::Recovery::Stop])
+	}
+
+	fn next_recovery(&mut self) {
+		let recovery = self.recoveries.next();
+		info!("extinction recovery: {:?}", recovery);
+		self.world.set_recovery(recovery);
+	}
+
+	fn toggle_flow(&mut self) {
+		let flow = self.world.flow_mut();
+		flow.enabled = !flow.enabled;
+		info!("flow: {}", flow.enabled);
+	}
+
+	fn toggle_topology(&mut self) {
+		let topology = match self.world.topology() {
+			world::Topology::Bounded => world::Topology::Toroidal,
+			world::Topology::Toroidal => world::Topology::Bounded,
+		};
+		info!("topology: {:?}", topology);
+		self.world.set_topology(topology);
+	}
+
 	pub fn pick_minion(&self, pos: Position) -> Option<Id> {
-		self.systems.physics.pick(pos)
+		self.systems.physics.pick(pos).or_else(|| self.systems.ai.pick(pos))
 	}
 
 	fn randomize_minion(&mut self, pos: Position) {
@@ -273,6 +479,20 @@ impl App {
 				self.backgrounds.prev();
 			}
 			Event::ToggleDebug => self.debug_flags.toggle(DEBUG_TARGETS),
+			Event::ToggleNavigation => self.systems.ai.toggle_navigation(),
+			Event::TogglePredation => self.systems.alife.toggle_predation(),
+			Event::NextMatingSystem => self.next_mating_system(),
+			Event::ToggleDormancy => self.systems.alife.toggle_dormancy(),
+			Event::NextRecovery => self.next_recovery(),
+			Event::ToggleDrag => self.systems.physics.toggle_drag(),
+			Event::ToggleFlow => self.toggle_flow(),
+			Event::ToggleTopology => self.toggle_topology(),
+			Event::NextTimeScale => {
+				info!("time scale: {}", self.time_scales.next());
+			}
+			Event::PrevTimeScale => {
+				info!("time scale: {}", self.time_scales.prev());
+			}
 			Event::Reload => {}
 
 			Event::AppQuit => self.quit(),
@@ -301,6 +521,7 @@ impl App {
 	}
 
 	pub fn quit(&mut self) {
+		self.world.save_hall_of_fame();
 		self.is_running = false;
 	}
 
@@ -341,6 +562,18 @@ impl App {
 			KpHome -> CamReset,
 			F6 -> DumpToFile,
 			D -> ToggleDebug,
+			S -> ToggleNavigation,
+			P -> TogglePredation,
+			G -> NextMatingSystem,
+			H -> ToggleDormancy,
+			X -> NextRecovery,
+			F -> ToggleDrag,
+			W -> ToggleFlow,
+			T -> ToggleTopology,
+			Plus -> NextTimeScale,
+			KpPlus -> NextTimeScale,
+			Minus -> PrevTimeScale,
+			KpMinus -> PrevTimeScale,
 			Z -> DeselectAll,
 			L -> NextLight,
 			B -> NextBackground,
@@ -423,8 +656,9 @@ impl App {
 			for (_, agent) in swarm.agents().iter() {
 				let energy_left = agent.state.energy_ratio();
 				let age = agent.state.lifecycle().seconds();
-				for segment in agent.segments() {
-					let body_transform = Self::from_transform(&segment.transform());
+				for segment in agent.segments().iter().filter(|s| !s.state.is_severed()) {
+					let key = agent::Key::with_segment(agent.id(), segment.index);
+					let body_transform = Self::from_transform(&self.interpolate(&key, segment.transform()));
 
 					let mesh = &segment.mesh();
 					let fixture_scale = Matrix4::from_scale(mesh.shape.radius());
@@ -454,6 +688,39 @@ impl App {
 		}
 	}
 
+	// where a segment is between its last two steps, going the short way round on a torus
+	fn interpolate(&self, key: &agent::Key, current: &Transform) -> Transform {
+		match self.previous.get(key) {
+			Some(previous) => {
+				let alpha = self.accumulator / self.timestep.step;
+				let d = self.world.displacement(&previous.position, &current.position);
+				let turn = current.angle - previous.angle;
+				let turn = turn - (turn / (2. * consts::PI)).round() * 2. * consts::PI;
+				Transform::new(current.position - d * (1. - alpha),
+				               current.angle - turn * (1. - alpha))
+			}
+			None => current.clone(),
+		}
+	}
+
+	fn render_obstacles(&self, renderer: &mut render::Draw) {
+		let appearance = render::Appearance::rgba([0.3, 0.3, 0.3, 1.]);
+		for o in self.world.obstacles() {
+			let transform = Self::from_transform(o.transform());
+			match o.outline {
+				obstacle::Outline::Circle { radius } => {
+					renderer.draw_ball(&(transform * Matrix4::from_scale(radius)), &appearance);
+				}
+				obstacle::Outline::Polygon { ref vertices } => {
+					renderer.draw_star(&transform, vertices, &appearance);
+				}
+				obstacle::Outline::Chain { ref vertices } => {
+					renderer.draw_lines(&transform, vertices, &appearance);
+				}
+			}
+		}
+	}
+
 	fn render_extent(&self, renderer: &mut render::Draw) {
 		let extent = &self.world.extent;
 		let points = &[extent.min,
@@ -482,7 +749,8 @@ impl App {
 					let p0 = sensor.transform.position;
 					let a0 = sensor.transform.angle;
 					let radar_range = sensor.mesh.shape.radius() * 10.;
-					let p1 = *agent.state.target_position();
+					// across the edge when that is the shorter way
+					let p1 = p0 + self.world.displacement(&p0, agent.state.target_position());
 					renderer.draw_debug_lines(&Matrix4::identity(),
 					                          &[p0, p1],
 					                          &render::Appearance::rgba([1., 1., 0., 1.]));
@@ -503,6 +771,14 @@ impl App {
 					                          &[p0, p3],
 					                          &render::Appearance::rgba([0., 1., 0., 1.]));
 
+					let vision = agent.vision();
+					for (i, hit) in agent.state.sight().iter().enumerate() {
+						let angle = a0 + vision.ray_angle(i);
+						let p4 = p0 + Position::new(angle.sin(), -angle.cos()) * hit.distance;
+						let color = if hit.agent_type.is_some() { [1., 0., 1., 1.] } else { [0.2, 0.2, 0.2, 1.] };
+						renderer.draw_debug_lines(&Matrix4::identity(), &[p0, p4], &render::Appearance::rgba(color));
+					}
+
 					let trajectory = agent.state.trajectory();
 					let appearance = render::Appearance::new(sensor.color(), [2.0, 1.0, 0., 0.]);
 					renderer.draw_debug_lines(&Matrix4::identity(), &trajectory, &appearance);
@@ -534,6 +810,7 @@ impl App {
 
 	pub fn render(&self, renderer: &mut render::Draw) {
 		self.render_minions(renderer);
+		self.render_obstacles(renderer);
 		self.render_extent(renderer);
 		self.render_hud(renderer);
 	}
@@ -571,6 +848,34 @@ impl App {
 		                      &|s, mut world| s.update_world(&mut world, dt));
 	}
 
+	pub fn set_timestep(&mut self, timestep: Timestep) {
+		self.timestep = timestep;
+	}
+
+	fn remember_transforms(&mut self) {
+		self.previous.clear();
+		for (_, swarm) in self.world.swarms().iter() {
+			for (&id, agent) in swarm.agents().iter() {
+				for segment in agent.segments() {
+					self.previous.insert(agent::Key::with_segment(id, segment.index), segment.transform().clone());
+				}
+			}
+		}
+	}
+
+	// one fixed step of the whole backend
+	fn tick(&mut self) {
+		self.cleanup();
+		self.remember_transforms();
+		let step = self.timestep.step;
+		self.update_systems(step);
+		self.register_all();
+		self.world.close_ledger();
+		self.ledger_rate.push(step, self.world.ledger());
+		// lifecycles and emitters run on this clock, so they keep pace at any time scale
+		advance_simulation(step);
+	}
+
 	pub fn update(&mut self) -> Update {
 		let frame_time = self.frame_start.seconds();
 		let frame_time_smooth = self.frame_smooth.smooth(frame_time);
@@ -578,15 +883,44 @@ impl App {
 		self.frame_elapsed += frame_time;
 		self.frame_start.reset();
 
-		self.cleanup();
-
 		self.camera.update(frame_time_smooth);
 
 		self.update_input(frame_time_smooth);
-		self.update_systems(frame_time_smooth);
-		self.register_all();
+
+		match self.time_scales.get() {
+			TimeScale::Times(k) => {
+				self.accumulator += frame_time * k;
+				let max_substeps = self.timestep.max_substeps * k.ceil() as usize;
+				let mut substeps = 0;
+				while self.accumulator >= self.timestep.step && substeps < max_substeps {
+					self.tick();
+					self.accumulator -= self.timestep.step;
+					substeps += 1;
+				}
+				// too far behind to catch up, drop the backlog
+				if self.accumulator >= self.timestep.step {
+					self.accumulator = self.accumulator % self.timestep.step;
+				}
+			}
+			TimeScale::Max => {
+				let budget = SystemStopwatch::new();
+				self.tick();
+				while budget.seconds() < MAX_SPEED_BUDGET && !self.world.is_stopped() {
+					self.tick();
+				}
+				self.accumulator = 0.;
+			}
+		}
 		self.frame_count += 1;
 
+		if self.world.is_stopped() && self.is_running {
+			info!("run stopped after {} extinctions, {} frames, {:.1}s",
+			      self.world.extinctions(),
+			      self.frame_count,
+			      self.wall_clock_start.seconds());
+			self.quit();
+		}
+
 		Update {
 			wall_clock_elapsed: self.wall_clock_start.seconds(),
 			frame_count: self.frame_count,
@@ -595,7 +929,11 @@ impl App {
 			frame_time_smooth: frame_time_smooth,
 			fps: 1.0 / frame_time_smooth,
 			population: self.world.agents(agent::AgentType::Minion).len(),
+			genders: self.world.gender_distribution(),
 			extinctions: self.world.extinctions(),
+			ledger: self.ledger_rate.per_second(),
+			time_scale: self.time_scales.get(),
+			simulated_time: simulated_seconds() as f32,
 		}
 	}
 }
@@ -225,6 +225,22 @@ impl Mesh {
 	pub fn winding(&self) -> Winding {
 		if self.flags.contains(CW) { Winding::CW } else { Winding::CCW }
 	}
+
+	// vertices are unit sized and go around the origin, so the shoelace formula works for stars too
+	pub fn area(&self) -> f32 {
+		let radius = self.shape.radius();
+		match self.shape {
+			Shape::Ball { .. } => PI * radius * radius,
+			_ => {
+				let n = self.vertices.len();
+				let twice_area = (0..n).fold(0., |a, i| {
+					let (p0, p1) = (self.vertices[i], self.vertices[(i + 1) % n]);
+					a + p0.x * p1.y - p1.x * p0.y
+				});
+				0.5 * twice_area.abs() * radius * radius
+			}
+		}
+	}
 }
 
 pub trait Identified {
@@ -241,6 +257,8 @@ pub struct Material {
 	pub density: f32,
 	pub restitution: f32,
 	pub friction: f32,
+	// how quickly spinning dies down
+	pub angular_damping: f32,
 }
 
 #[derive(Clone)]
@@ -258,6 +276,7 @@ impl Default for Material {
 			density: 1.0,
 			restitution: 0.6,
 			friction: 0.7,
+			angular_damping: 0.9,
 		}
 	}
 }
@@ -286,3 +305,49 @@ pub trait Geometry {
 pub trait Drawable: Geometry {
 	fn color(&self) -> Rgba;
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::f32::consts::PI;
+
+	fn area_of(shape: Shape) -> f32 {
+		Mesh::from_shape(shape, Winding::CW).area()
+	}
+
+	fn assert_close(a: f32, b: f32) {
+		assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+	}
+
+	#[test]
+	fn ball_area_is_exact() {
+		assert_close(area_of(Shape::new_ball(2.)), 4. * PI);
+	}
+
+	#[test]
+	fn box_area_scales_with_the_radius() {
+		assert_close(area_of(Shape::new_box(1., 0.5)), 2.);
+		assert_close(area_of(Shape::new_box(2., 0.5)), 8.);
+	}
+
+	#[test]
+	fn area_does_not_depend_on_winding() {
+		let shape = Shape::new_triangle(1., 2. * PI / 3., 4. * PI / 3.);
+		let cw = Mesh::from_shape(shape.clone(), Winding::CW).area();
+		let ccw = Mesh::from_shape(shape, Winding::CCW).area();
+		assert_close(cw, ccw);
+		assert_close(cw, 3. * f32::sqrt(3.) / 4.);
+	}
+
+	#[test]
+	fn square_poly_area() {
+		// the odd vertices sit on the edges between the corners
+		assert_close(area_of(Shape::new_poly(4, 1.)), 2.);
+	}
+
+	#[test]
+	fn star_area_is_positive_and_less_than_its_circle() {
+		let area = area_of(Shape::new_star(5, 1., 0.5, 1.));
+		assert!(area > 0. && area < PI);
+	}
+}
@@ -10,56 +10,138 @@ use backend::world::agent::Personality;
 use backend::world::agent::TypedAgent;
 use backend::world::segment;
 use backend::world::segment::Intent;
+use backend::world::scent::Channel;
 use cgmath::*;
 use core::geometry::Position;
+use core::geometry::Rect;
+use core::spatial::GridIndex;
 use itertools::Itertools;
 
 type IdPositionMap = HashMap<obj::Id, Position>;
+// food and minion scent gradients at each sensor
+type ScentMap = HashMap<obj::Id, (Position, Position)>;
+
+// where minions head to when there is no food within sensor range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Navigation {
+	// straight to the nearest emitter, wherever it is
+	Beacon,
+	// up the food scent gradient
+	Scent,
+}
+
+const INDEX_CELL_SIZE: f32 = 8.;
+const PICK_RADIUS: f32 = 2.;
 
 pub struct AiSystem {
+	navigation: Navigation,
+	topology: world::Topology,
+	extent: Rect,
 	beacons: Box<[Position]>,
+	scents: ScentMap,
 	targets: IdPositionMap,
+	target_index: GridIndex<obj::Id>,
+	minion_index: GridIndex<obj::Id>,
 }
 
 impl Updateable for AiSystem {}
 
 impl System for AiSystem {
+	fn init(&mut self, world: &world::World) {
+		self.extent = world.extent;
+		self.target_index = GridIndex::new(&world.extent, INDEX_CELL_SIZE);
+		self.minion_index = GridIndex::new(&world.extent, INDEX_CELL_SIZE);
+	}
+
 	fn from_world(&mut self, world: &world::World) {
+		self.topology = world.topology();
+		let wrapping = self.topology == world::Topology::Toroidal;
+		self.target_index.set_wrapping(wrapping);
+		self.minion_index.set_wrapping(wrapping);
 		self.beacons = world.emitters().iter().map(|e| e.transform().position).collect::<Vec<_>>().into_boxed_slice();
 		self.targets = world.agents(agent::AgentType::Resource)
 			.iter()
 			.filter(|&(_, ref v)| v.state.is_active())
 			.map(|(_, v)| (v.id(), v.transform().position))
 			.collect::<HashMap<_, _>>();
+		self.target_index.clear();
+		for (&id, &position) in self.targets.iter() {
+			self.target_index.insert(position, id);
+		}
+		self.minion_index.clear();
+		for (&id, minion) in world.agents(agent::AgentType::Minion).iter().filter(|&(_, a)| a.state.is_active()) {
+			for segment in minion.segments().iter().filter(|s| !s.state.is_severed()) {
+				self.minion_index.insert(segment.transform.position, id);
+			}
+		}
+		self.scents.clear();
+		for (&id, minion) in world.agents(agent::AgentType::Minion).iter() {
+			if let Some(sensor) = minion.first_segment(segment::SENSOR) {
+				let p = &sensor.transform.position;
+				self.scents.insert(id,
+				                   (world.scent().gradient(Channel::Food, p), world.scent().gradient(Channel::Minion, p)));
+			}
+		}
 	}
 
 	fn to_world(&self, world: &mut world::World) {
-		Self::update_minions(&self.targets,
+		Self::update_minions(self.navigation,
+		                     self.topology,
+		                     &self.extent,
+		                     &self.targets,
+		                     &self.target_index,
 		                     &self.beacons,
+		                     &self.scents,
 		                     &mut world.agents_mut(agent::AgentType::Minion));
 	}
 }
 
 impl Default for AiSystem {
 	fn default() -> Self {
+		let extent = Rect::new(-1., -1., 1., 1.);
 		AiSystem {
+			navigation: Navigation::Beacon,
+			topology: world::Topology::Bounded,
+			extent: extent,
 			beacons: Box::new([]),
+			scents: HashMap::new(),
 			targets: HashMap::new(),
+			target_index: GridIndex::new(&extent, INDEX_CELL_SIZE),
+			minion_index: GridIndex::new(&extent, INDEX_CELL_SIZE),
 		}
 	}
 }
 
 impl AiSystem {
-	fn update_minions(targets: &IdPositionMap, beacons: &[Position], minions: &mut agent::AgentMap) {
+	pub fn toggle_navigation(&mut self) {
+		self.navigation = match self.navigation {
+			Navigation::Beacon => Navigation::Scent,
+			Navigation::Scent => Navigation::Beacon,
+		};
+		info!("navigation: {:?}", self.navigation);
+	}
 
-		fn nearest_beacon<'a>(beacons: &'a [Position], p: &'a Position) -> &'a Position {
+	// nearest minion segment to the given position, more forgiving than an exact fixture hit
+	pub fn pick(&self, pos: Position) -> Option<obj::Id> {
+		self.minion_index.nearest_within(&pos, PICK_RADIUS).map(|(id, _)| id)
+	}
+
+	fn update_minions(navigation: Navigation, topology: world::Topology, extent: &Rect, targets: &IdPositionMap,
+	                  target_index: &GridIndex<obj::Id>, beacons: &[Position], scents: &ScentMap,
+	                  minions: &mut agent::AgentMap) {
+
+		let distance2 = |p: &Position, q: &Position| topology.displacement(extent, p, q).length2();
+		let nearest_beacon = |p: &Position| -> Position {
 			beacons.iter()
-				.fold1(|n, b| if (p - n).length2() < (p - b).length2() { n } else { b })
-				.unwrap_or(p)
-		}
+				.fold1(|n, b| if distance2(p, n) < distance2(p, b) { n } else { b })
+				.map(|b| *b)
+				.unwrap_or(*p)
+		};
 
 		for (_, agent) in minions.iter_mut() {
 			let brain = agent.brain().clone();
+			let (food_scent, minion_scent) =
+				scents.get(&agent.id()).cloned().unwrap_or((Position::new(0., 0.), Position::new(0., 0.)));
 			let core = agent.first_segment(segment::CORE);
 			let head = agent.first_segment(segment::SENSOR);
 			if let Some(sensor) = head {
@@ -69,48 +151,78 @@ impl AiSystem {
 				let current_target_position = agent.state.target_position().clone();
 				// if our original target is dead then we need to find another one
 				let new_target: Option<(obj::Id, Position)> = match current_target {
-					None => {
-						targets.iter()
-							.find(|&(_, &p)| (p - p0).length() < radar_range)
-							.map(|(&id, &position)| (id, position))
-					}
+					None => target_index.nearest_within(&p0, radar_range),
 					Some(id) => targets.get(&id).map(|&position| (id, position)),
 				};
-				// and failing that again, we target
+				// and failing that again, we follow the scent if we can smell anything, or target the nearest beacon
 				match new_target {
-					None => agent.state.retarget(None, *nearest_beacon(beacons, &current_target_position)),
+					None => {
+						if navigation == Navigation::Scent && food_scent.length2() > 0. {
+							agent.state.retarget(None, p0 + food_scent.normalize_to(radar_range))
+						} else {
+							agent.state.retarget(None, nearest_beacon(&current_target_position))
+						}
+					}
 					Some((id, position)) => agent.state.retarget(Some(id), position),
 				};
 				// find where our target is in the world
 				let target_position = agent.state.target_position().clone();
 				// and transform the world position into the head's frame
-				let t0 = target_position - sensor.transform.position;
+				let t0 = topology.displacement(extent, &sensor.transform.position, &target_position);
 				let t = t0.normalize_to(t0.length().min(radar_range));
 				// direction in which the head is pointing, normalized
 				let s = Matrix2::from_angle(rad(sensor.transform.angle)) * (-Position::unit_y());
 				// some proprioception, feeding back the angle betweent the neck and the first torso
 				let neck_angle = consts::PI + sensor.transform.angle -
 				                 core.map(|t| t.transform.angle).unwrap_or(sensor.transform.angle);
+				// what the vision cone sees, as a single signed stimulus: obstacles on the left are positive,
+				// on the right negative, stronger the closer they are. Food is not an obstacle
+				let vision = agent.vision().clone();
+				let obstacle = agent.state
+					.sight()
+					.iter()
+					.enumerate()
+					.filter(|&(_, hit)| match hit.agent_type {
+						None | Some(agent::AgentType::Resource) => false,
+						_ => true,
+					})
+					.fold(0., |a, (i, hit)| {
+						let side = if vision.fov > 0. { vision.ray_angle(i) / (vision.fov * 0.5) } else { 0. };
+						a + side * (1. - hit.distance / vision.range)
+					});
 				// we pass the relative position of the target decomposed in our frame of reference to the neural network
 				// expecting four components we can use as thresholds
-				let r = agent.brain().response(&[neck_angle, t.dot(s), t.perp_dot(s), 0.]);
+				// and what we smell, ahead and to the side, saturating for strong scents
+				let smell = |g: &Position| {
+					let l = g.length();
+					if l > 0. { g * (1. / (1. + l)) } else { *g }
+				};
+				let (food, kin) = (smell(&food_scent), smell(&minion_scent));
+				let r = agent.brain().response(&[neck_angle, t.dot(s), t.perp_dot(s), obstacle],
+				                               &[food.dot(s), food.perp_dot(s), kin.dot(s), kin.perp_dot(s)]);
 				const POWER_BOOST: f32 = 100.;
+				const TORQUE_BOOST: f32 = 50.;
+				const MAX_MOTOR_SPEED: f32 = 2. * consts::PI;
+				// actuators weaken with old age
+				let vigour = 1. - agent.state.senescence();
 
 				let segments = &mut agent.segments_mut();
 				for segment in segments.iter_mut() {
 					let flags = &segment.flags;
-					if flags.contains(segment::ACTUATOR) {
+					if flags.contains(segment::ACTUATOR) && !segment.state.is_severed() {
 						let power = segment.state.get_charge() * segment.mesh.shape.radius().powi(2) * POWER_BOOST;
 						let f = Matrix2::from_angle(rad(segment.transform.angle)) * Position::unit_y() * power;
+						// anything but food touching us makes us bolt
+						let threatened = segment.state
+							.contacts()
+							.iter()
+							.any(|c| c.other.id().type_of() != agent::AgentType::Resource);
 						let intent =
-							if let Some(refs) = segment.state.last_touched {
-								match refs.id().type_of() {
-									agent::AgentType::Resource => Intent::Idle,
-									_ => {
-										let fear: f32 = brain.fear();
-										Intent::RunAway(f * fear)
-									}
-								}
+							if threatened {
+								let fear: f32 = brain.fear();
+								Intent::RunAway(f * fear)
+							} else if !segment.state.contacts().is_empty() {
+								Intent::Idle
 							} else if flags.contains(segment::RUDDER | segment::LEFT) && r[0] > brain.hunger() {
 								Intent::Move(-f)
 							} else if flags.contains(segment::RUDDER | segment::RIGHT) && r[1] > brain.hunger() {
@@ -124,12 +236,25 @@ impl AiSystem {
 							};
 						match intent {
 							Intent::Idle => segment.state.set_target_charge(brain.rest()),
-							Intent::Move(_) => segment.state.set_target_charge(brain.thrust()),
-							Intent::Brake(_) => segment.state.set_target_charge(brain.thrust()),
-							Intent::RunAway(_) => segment.state.set_charge(brain.thrust()),
+							Intent::Move(_) => segment.state.set_target_charge(brain.thrust() * vigour),
+							Intent::Brake(_) => segment.state.set_target_charge(brain.thrust() * vigour),
+							Intent::RunAway(_) => segment.state.set_charge(brain.thrust() * vigour),
 						}
 						segment.state.intent = intent;
 					}
+					// motors swing their limb as fast as the brain says, left and right mirrored
+					if segment.flags.contains(segment::MOTOR) && !segment.state.is_severed() {
+						let output = if segment.flags.contains(segment::LEFT) {
+							r[0]
+						} else if segment.flags.contains(segment::RIGHT) {
+							-r[1]
+						} else {
+							r[2] - r[3]
+						};
+						segment.state.motor_speed = output * MAX_MOTOR_SPEED;
+						segment.state.motor_torque = brain.thrust() * vigour * segment.mesh.shape.radius().powi(2) *
+						                             TORQUE_BOOST;
+					}
 				}
 			}
 		}
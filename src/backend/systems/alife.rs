@@ -1,25 +1,116 @@
 use super::*;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use rand;
+use rand::Rng;
+use std::f32::consts;
+use cgmath::EuclideanVector;
 use core::geometry;
+use core::geometry::Rect;
+use core::spatial::GridIndex;
 use backend::obj;
 use backend::obj::Transformable;
 use backend::obj::Identified;
 use backend::world;
 use backend::world::gen;
 use backend::world::agent;
+use backend::world::agent::Personality;
 use backend::world::segment;
 use backend::world::WorldState;
+use backend::world::gender::Genders;
+use backend::world::ledger::Ledger;
+use backend::world::swarm::CapPolicy;
 use serialize::base64::{self, ToBase64};
 
-type StateMap = HashMap<obj::Id, agent::State>;
-type GeneMap = HashMap<obj::Id, gen::Dna>;
+// eaten resources by id, with the minion who ate each one
+type EatenMap = HashMap<obj::Id, (obj::Id, agent::State)>;
+// spores and the mate whose genes they accepted
+type MateMap = HashMap<obj::Id, (obj::Id, gen::Dna)>;
+// who laid a new spore, where, how fast it flies off, and how much energy it carries
+type Spawn = (obj::Id, geometry::Transform, geometry::Motion, f32, gen::Dna);
+// predator, victim and how much energy per second the bite takes
+type Bite = (obj::Id, obj::Id, f32);
+
+const INDEX_CELL_SIZE: f32 = 8.;
+// largest radius a minion segment can develop
+const MAX_SEGMENT_RADIUS: f32 = 2.;
+// contact impulse a segment can take without damage
+const IMPULSE_THRESHOLD: f32 = 5.;
+// health lost per unit of impulse above threshold, for a segment of unit radius
+const DAMAGE_SCALE: f32 = 0.02;
+// below this a minion starves to death
+const MIN_ENERGY: f32 = 1.;
+// share of its storage a parent keeps back after laying
+const RESERVE_RATIO: f32 = 0.1;
+// energy per second a fully aggressive mouth of unit radius takes from its prey
+const BITE_RATE: f32 = 20.;
+
+// Spores that are due to hatch can wait until the world around them looks promising
+#[derive(Clone)]
+pub struct Dormancy {
+	pub enabled: bool,
+	// how much food there must be around
+	pub min_resources: usize,
+	pub resource_radius: f32,
+	// how far away from mother and father
+	pub min_parent_distance: f32,
+	// no point hatching into a crowd
+	pub max_population: usize,
+	// seconds past hatching time after which the spore rots
+	pub max_dormancy: f32,
+}
+
+impl Default for Dormancy {
+	fn default() -> Self {
+		Dormancy {
+			enabled: false,
+			min_resources: 3,
+			resource_radius: 10.,
+			min_parent_distance: 10.,
+			max_population: 100,
+			max_dormancy: 60.,
+		}
+	}
+}
+
+// What it costs a minion to stay alive, per second
+#[derive(Clone)]
+pub struct Metabolism {
+	// per unit of body mass, active or not
+	pub basal: f32,
+	// per unit of force applied by moving or braking
+	pub actuation: f32,
+	// per unit of synaptic weight
+	pub brain: f32,
+	// per unit of work done by joint motors
+	pub motor: f32,
+}
+
+impl Default for Metabolism {
+	fn default() -> Self {
+		Metabolism {
+			basal: 0.2,
+			actuation: 0.01,
+			brain: 0.02,
+			motor: 0.005,
+		}
+	}
+}
 
 pub struct AlifeSystem {
 	dt: f32,
 	source: Box<[world::Emitter]>,
-	eaten: StateMap,
-	touched: GeneMap,
+	eaten: EatenMap,
+	touched: MateMap,
+	predation: bool,
+	bites: Vec<Bite>,
+	dormancy: Dormancy,
+	awake: HashSet<obj::Id>,
+	minion_index: GridIndex<agent::Key>,
+	resource_index: GridIndex<obj::Id>,
+	crowding: f32,
+	metabolism: Metabolism,
+	topology: world::Topology,
 }
 
 impl Updateable for AlifeSystem {
@@ -29,34 +120,92 @@ impl Updateable for AlifeSystem {
 }
 
 impl System for AlifeSystem {
+	fn init(&mut self, world: &world::World) {
+		self.minion_index = GridIndex::new(&world.extent, INDEX_CELL_SIZE);
+		self.resource_index = GridIndex::new(&world.extent, INDEX_CELL_SIZE);
+	}
+
 	fn from_world(&mut self, world: &world::World) {
 		self.source = world.emitters().to_vec().into_boxed_slice();
+		self.topology = world.topology();
+		let wrapping = self.topology == world::Topology::Toroidal;
+		self.minion_index.set_wrapping(wrapping);
+		self.resource_index.set_wrapping(wrapping);
+		let minions = world.swarm(&agent::AgentType::Minion);
+		self.crowding = if minions.capacity().policy == CapPolicy::Tax { minions.crowding() } else { 0. };
 		self.eaten = Self::find_eaten_resources(&world.agents(agent::AgentType::Minion),
 		                                        &world.agents(agent::AgentType::Resource));
+		self.bites = if self.predation {
+			Self::find_bitten_minions(&world.agents(agent::AgentType::Minion))
+		} else {
+			Vec::new()
+		};
+		self.minion_index.clear();
+		for (&id, minion) in world.agents(agent::AgentType::Minion).iter().filter(|&(_, a)| a.state.is_active()) {
+			for segment in minion.segments().iter().filter(|s| !s.state.is_severed()) {
+				self.minion_index.insert(segment.transform.position, agent::Key::with_segment(id, segment.index));
+			}
+		}
+		self.awake = if self.dormancy.enabled {
+			self.resource_index.clear();
+			for (&id, resource) in world.agents(agent::AgentType::Resource).iter().filter(|&(_, a)| a.state.is_active()) {
+				self.resource_index.insert(resource.transform().position, id);
+			}
+			Self::find_awake_spores(world.topology(),
+			                        &world.extent,
+			                        world.swarm(&agent::AgentType::Minion).population(),
+			                        &world.agents(agent::AgentType::Minion),
+			                        &world.agents(agent::AgentType::Spore),
+			                        &self.resource_index,
+			                        &self.dormancy)
+		} else {
+			HashSet::new()
+		};
 		self.touched = Self::find_touched_spores(&world.agents(agent::AgentType::Minion),
-		                                         &world.agents(agent::AgentType::Spore));
+		                                         &world.agents(agent::AgentType::Spore),
+		                                         &self.minion_index,
+		                                         world.genders());
 	}
 
 	fn to_world(&self, world: &mut world::World) {
+		let mut ledger = Ledger::default();
 		Self::update_resources(self.dt,
 		                       &mut world.agents_mut(agent::AgentType::Resource),
 		                       &self.eaten);
 
+		Self::update_bites(self.dt,
+		                   &mut world.agents_mut(agent::AgentType::Minion),
+		                   &self.bites,
+		                   &mut ledger);
+
 		let (spores, corpses) = Self::update_minions(self.dt,
 		                                             &world.extent.clone(),
+		                                             self.topology,
+		                                             self.crowding,
+		                                             &self.metabolism,
 		                                             &mut world.agents_mut(agent::AgentType::Minion),
-		                                             &self.eaten);
-		let hatch = Self::update_spores(self.dt,
-		                                &mut world.agents_mut(agent::AgentType::Spore),
-		                                &self.touched);
+		                                             &self.eaten,
+		                                             &mut ledger);
+		let (hatch, rotten) = Self::update_spores(self.dt,
+		                                          &mut world.agents_mut(agent::AgentType::Spore),
+		                                          &self.touched,
+		                                          &self.dormancy,
+		                                          &self.awake);
+		Self::update_mates(&mut world.agents_mut(agent::AgentType::Minion), &self.touched, &mut ledger);
 
-		for &(ref transform, ref dna) in spores.into_iter() {
-			world.new_spore(transform, dna);
+		// offspring refused by a full swarm take their energy with them
+		for &(parent, ref transform, ref motion, energy, ref dna) in spores.into_iter() {
+			if world.new_spore(parent, transform, Some(motion), energy, dna).is_none() {
+				ledger.waste += energy;
+			}
 		}
-		for &(ref transform, ref dna) in hatch.into_iter() {
-			world.hatch_spore(transform, dna);
+		for &(ref transform, energy, ref dna) in hatch.into_iter() {
+			if world.hatch_spore(transform, energy, dna).is_none() {
+				ledger.waste += energy;
+			}
 		}
-		for &(ref transform, ref dna) in corpses.into_iter() {
+		world.ledger_mut().merge(&ledger);
+		for &(ref transform, ref dna) in corpses.iter().chain(rotten.iter()) {
 			world.decay_to_resource(transform, dna);
 		}
 	}
@@ -67,20 +216,118 @@ impl Default for AlifeSystem {
 		AlifeSystem {
 			dt: 1. / 60.,
 			source: Box::new([]),
-			eaten: StateMap::new(),
-			touched: GeneMap::new(),
+			eaten: EatenMap::new(),
+			touched: MateMap::new(),
+			predation: false,
+			bites: Vec::new(),
+			dormancy: Dormancy::default(),
+			awake: HashSet::new(),
+			minion_index: GridIndex::new(&Rect::new(-1., -1., 1., 1.), INDEX_CELL_SIZE),
+			resource_index: GridIndex::new(&Rect::new(-1., -1., 1., 1.), INDEX_CELL_SIZE),
+			crowding: 0.,
+			metabolism: Metabolism::default(),
+			topology: world::Topology::Bounded,
 		}
 	}
 }
 
 impl AlifeSystem {
-	fn find_eaten_resources(minions: &agent::AgentMap, resources: &agent::AgentMap) -> StateMap {
+	pub fn set_metabolism(&mut self, metabolism: Metabolism) {
+		self.metabolism = metabolism;
+	}
+
+	pub fn toggle_predation(&mut self) {
+		self.predation = !self.predation;
+		info!("predation: {}", self.predation);
+	}
+
+	pub fn toggle_dormancy(&mut self) {
+		self.dormancy.enabled = !self.dormancy.enabled;
+		info!("dormancy: {}", self.dormancy.enabled);
+	}
+
+	// spores which would be happy to hatch right now
+	fn find_awake_spores(topology: world::Topology, extent: &geometry::Rect, population: usize,
+	                     minions: &agent::AgentMap, spores: &agent::AgentMap, resource_index: &GridIndex<obj::Id>,
+	                     dormancy: &Dormancy)
+	                     -> HashSet<obj::Id> {
+		let mut awake = HashSet::new();
+		if population >= dormancy.max_population {
+			return awake;
+		}
+		for (&id, spore) in spores.iter().filter(|&(_, a)| a.state.is_active() && a.state.lifecycle().is_expired()) {
+			let p0 = spore.transform().position;
+			let food = resource_index.within(&p0, dormancy.resource_radius).len();
+			// dead parents are as far as they can be
+			let near_parents = spore.state
+				.parents()
+				.iter()
+				.filter_map(|parent| minions.get(parent))
+				.filter(|parent| parent.state.is_alive())
+				.any(|parent| {
+					topology.displacement(extent, &parent.transform().position, &p0).length() <
+					dormancy.min_parent_distance
+				});
+			if food >= dormancy.min_resources && !near_parents {
+				awake.insert(id);
+			}
+		}
+		awake
+	}
+
+	// a mouth touching another minion anywhere but its core takes a bite,
+	// as big as the mouth and as strong as the predator's aggression
+	fn find_bitten_minions(minions: &agent::AgentMap) -> Vec<Bite> {
+		let mut bites = Vec::new();
+		// one bite per mouth and victim segment, however many fixtures touch
+		let mut bitten = HashSet::new();
+		for (&id, predator) in minions.iter().filter(|&(_, a)| a.state.is_active()) {
+			let aggression: f32 = predator.brain().aggression();
+			for segment in predator.segments
+				.iter()
+				.filter(|&s| s.flags.contains(segment::MOUTH) && !s.state.is_severed()) {
+				for key in segment.state.contacts().iter().map(|c| c.other.no_bone()) {
+					if !bitten.insert((id, segment.index, key)) {
+						continue;
+					}
+					if let Some(victim) = minions.get(&key.id()) {
+						let is_core = victim.segment(key.segment_index)
+							.map(|s| s.flags.contains(segment::CORE))
+							.unwrap_or(true);
+						if key.id() != id && victim.state.is_active() && !is_core {
+							let rate = aggression * segment.mesh.shape.radius().powi(2) * BITE_RATE;
+							bites.push((id, key.id(), rate));
+						}
+					}
+				}
+			}
+		}
+		bites
+	}
+
+	fn update_bites(dt: f32, minions: &mut agent::AgentMap, bites: &[Bite], ledger: &mut Ledger) {
+		for &(predator_id, victim_id, rate) in bites {
+			let bitten = match minions.get_mut(&victim_id) {
+				Some(victim) => victim.state.drain(rate * dt),
+				None => 0.,
+			};
+			match minions.get_mut(&predator_id) {
+				Some(predator) => ledger.waste += predator.state.absorb(bitten),
+				None => ledger.waste += bitten,
+			}
+		}
+	}
+
+	// a resource touched by several mouths goes whole to the first one found
+	fn find_eaten_resources(minions: &agent::AgentMap, resources: &agent::AgentMap) -> EatenMap {
 		let mut eaten = HashMap::new();
-		for (_, agent) in minions.iter().filter(|&(_, a)| a.state.is_active()) {
-			for segment in agent.segments.iter().filter(|&s| s.flags.contains(segment::MOUTH)) {
-				if let Some(key) = segment.state.last_touched {
+		for (&id, agent) in minions.iter().filter(|&(_, a)| a.state.is_active()) {
+			for segment in agent.segments
+				.iter()
+				.filter(|&s| s.flags.contains(segment::MOUTH) && !s.state.is_severed()) {
+				for key in segment.state.contacts().iter().map(|c| c.other) {
 					if let Some(&agent::Agent { ref state, .. }) = resources.get(&key.id()) {
-						eaten.insert(key.id(), (*state).clone());
+						eaten.entry(key.id()).or_insert_with(|| (id, (*state).clone()));
 					}
 				}
 			}
@@ -88,14 +335,26 @@ impl AlifeSystem {
 		eaten
 	}
 
-	fn find_touched_spores(minions: &agent::AgentMap, spores: &agent::AgentMap) -> GeneMap {
+	// a spore is touched when any minion segment overlaps it, keyed by spore id.
+	// The spore only takes the genes of mates it likes and who can afford the effort
+	fn find_touched_spores(minions: &agent::AgentMap, spores: &agent::AgentMap, minion_index: &GridIndex<agent::Key>,
+	                       genders: &Genders)
+	                       -> MateMap {
 		let mut touched = HashMap::new();
-		for (_, spore) in spores.iter().filter(|&(_, a)| a.state.is_active() && !a.state.is_fertilised()) {
+		// an expired spore hatches this tick anyway, mating with it would only cost the minion
+		for (&spore_id, spore) in spores.iter().filter(|&(_, a)| {
+			a.state.is_active() && !a.state.is_fertilised() && !a.state.lifecycle().is_expired()
+		}) {
 			for segment in spore.segments.iter() {
-				if let Some(key) = segment.state.last_touched {
+				let p0 = segment.transform.position;
+				let r0 = segment.mesh.shape.radius();
+				for &(key, p1) in minion_index.within(&p0, r0 + MAX_SEGMENT_RADIUS).iter() {
 					if let Some(ref agent) = minions.get(&key.id()) {
-						if agent.gender() != spore.gender() {
-							touched.insert(key.id(), agent.dna().clone());
+						let r1 = agent.segment(key.segment_index).map(|s| s.mesh.shape.radius()).unwrap_or(0.);
+						let effort = agent.reproduction().mating_effort * agent.state.max_energy();
+						if (p1 - p0).length() <= r0 + r1 && genders.compatible(spore.gender(), agent.gender()) &&
+						   agent.state.energy() > effort && spore.preference().accepts(agent) {
+							touched.insert(spore_id, (key.id(), agent.dna().clone()));
 						}
 					}
 				}
@@ -104,34 +363,72 @@ impl AlifeSystem {
 		touched
 	}
 
-	fn update_minions(dt: f32, extent: &geometry::Rect, minions: &mut agent::AgentMap, eaten: &StateMap)
-	                  -> (Box<[(geometry::Transform, gen::Dna)]>, Box<[(geometry::Transform, gen::Dna)]>) {
+	fn update_minions(dt: f32, extent: &geometry::Rect, topology: world::Topology, crowding: f32,
+	                  metabolism: &Metabolism, minions: &mut agent::AgentMap, eaten: &EatenMap, ledger: &mut Ledger)
+	                  -> (Box<[Spawn]>, Box<[(geometry::Transform, gen::Dna)]>) {
 		let mut spawns = Vec::new();
 		let mut corpses = Vec::new();
-		for (_, agent) in minions.iter_mut() {
+		let rng = &mut rand::thread_rng();
+		for (&id, agent) in minions.iter_mut() {
 			if agent.state.is_active() {
-				if agent.state.lifecycle().is_expired() && agent.state.consume_ratio(0.75) {
-					spawns.push((agent.last_segment().transform().clone(), agent.dna().clone()));
+				let reproduction = agent.reproduction().clone();
+				let investment = agent.state.max_energy() * reproduction.investment;
+				// laying must not leave the parent too weak to go on
+				let reserve = f32::max(MIN_ENERGY, agent.state.max_energy() * RESERVE_RATIO);
+				if agent.state.age() >= reproduction.maturity && agent.state.lifecycle().is_expired() &&
+				   agent.state.energy() >= investment + reserve && agent.state.consume(investment) {
+					// the investment is shared evenly among the clutch
+					let energy = investment / reproduction.clutch as f32;
+					for _ in 0..reproduction.clutch {
+						let angle = rng.next_f32() * 2. * consts::PI;
+						let motion = geometry::Motion {
+							velocity: geometry::Velocity::new(angle.cos(), angle.sin()) * reproduction.dispersal,
+							spin: 0.,
+						};
+						spawns.push((id, agent.last_segment().transform().clone(), motion, energy, agent.dna().clone()));
+					}
+					agent.state.add_offspring(reproduction.clutch);
 					agent.state.renew();
 				}
-				for segment in agent.segments.iter_mut() {
+				// a mouth can close on more than one morsel at a time
+				for &(_, ref eaten_state) in eaten.values().filter(|&&(eater, _)| eater == id) {
+					ledger.waste += agent.state.absorb(eaten_state.energy());
+				}
+				for (transform, dna) in Self::sever_damaged(agent) {
+					corpses.push((transform, dna));
+				}
+				for segment in agent.segments.iter_mut().filter(|s| !s.state.is_severed()) {
 					let p = segment.transform().position;
-					if p.x < extent.min.x || p.x > extent.max.x || p.y < extent.min.y || p.y > extent.max.y {
+					// on a torus physics brings them back in from the other side
+					if topology == world::Topology::Bounded &&
+					   (p.x < extent.min.x || p.x > extent.max.x || p.y < extent.min.y || p.y > extent.max.y) {
 						agent.state.die();
 					}
-					if segment.flags.contains(segment::MOUTH) {
-						if let Some(id) = segment.state.last_touched {
-							if let Some(eaten_state) = eaten.get(&id.id()) {
-								agent.state.absorb(eaten_state.energy());
-							}
-						}
+					let actuation = match segment.state.intent {
+						segment::Intent::Move(force) |
+						segment::Intent::Brake(force) => force.length(),
+						_ => 0.,
+					};
+					// old bodies are more expensive to run, and so is a crowded world
+					let upkeep = (1. + agent.state.senescence()) * (1. + crowding);
+					let cost = upkeep *
+					           (dt * (metabolism.basal * segment.mass() + metabolism.actuation * actuation) +
+					            metabolism.motor * segment.state.motor_work);
+					if agent.state.consume(cost) {
+						ledger.metabolic += cost;
 					}
-					agent.state.consume(dt * segment.state.get_charge() * segment.mesh.shape.radius());
 					segment.state.update(dt);
 				}
+				let thinking = dt * metabolism.brain * agent.brain().synapses();
+				if agent.state.consume(thinking) {
+					ledger.metabolic += thinking;
+				}
 
-				if agent.state.energy() < 1. {
-					for segment in agent.segments.iter().filter(|s| s.flags.contains(segment::STORAGE)) {
+				agent.state.grow_older(dt);
+				if agent.state.energy() < MIN_ENERGY || agent.state.is_too_old() || agent.segments[0].state.is_severed() {
+					for segment in agent.segments
+						.iter()
+						.filter(|s| s.flags.contains(segment::STORAGE) && !s.state.is_severed()) {
 						corpses.push((segment.transform.clone(), agent.dna().clone()));
 					}
 					agent.state.die();
@@ -145,9 +442,38 @@ impl AlifeSystem {
 		(spawns.into_boxed_slice(), corpses.into_boxed_slice())
 	}
 
-	fn update_resources(dt: f32, resources: &mut agent::AgentMap, eaten: &StateMap) {
+	// hard hits wear segments down, smaller ones faster. Once a segment is worn out
+	// it falls off together with everything attached to it, and rots into food
+	fn sever_damaged(agent: &mut agent::Agent) -> Vec<(geometry::Transform, gen::Dna)> {
+		let mut severed = Vec::new();
+		let dna = agent.dna().clone();
+		for i in 0..agent.segments.len() {
+			let parent_severed = agent.segments[i]
+				.attached_to
+				.map(|a| agent.segments[a.index as usize].state.is_severed())
+				.unwrap_or(false);
+			let segment = &mut agent.segments[i];
+			if segment.state.is_severed() {
+				continue;
+			}
+			let excess = segment.state.max_impulse() - IMPULSE_THRESHOLD;
+			if excess > 0. {
+				segment.state.damage(excess * DAMAGE_SCALE / segment.mesh.shape.radius().powi(2));
+			}
+			if parent_severed || segment.state.health() <= 0. {
+				segment.state.sever();
+				severed.push((segment.transform.clone(), dna.clone()));
+			}
+		}
+		severed
+	}
+
+	fn update_resources(dt: f32, resources: &mut agent::AgentMap, eaten: &EatenMap) {
 		for (_, agent) in resources.iter_mut() {
-			if eaten.get(&agent.id()).is_some() {
+			if eaten.contains_key(&agent.id()) {
+				// all of it went into the mouth that ate it
+				let energy = agent.state.energy();
+				agent.state.drain(energy);
 				agent.state.die();
 			} else if agent.state.energy() <= 0. {
 				agent.state.die();
@@ -168,30 +494,47 @@ impl AlifeSystem {
 		}
 	}
 
-	fn update_spores(dt: f32, spores: &mut agent::AgentMap, touched: &GeneMap)
-	                 -> Box<[(geometry::Transform, gen::Dna)]> {
+	fn update_mates(minions: &mut agent::AgentMap, touched: &MateMap, ledger: &mut Ledger) {
+		for &(mate_id, _) in touched.values() {
+			if let Some(mate) = minions.get_mut(&mate_id) {
+				let effort = mate.reproduction().mating_effort * mate.state.max_energy();
+				if mate.state.consume(effort) {
+					ledger.metabolic += effort;
+				}
+			}
+		}
+	}
+
+	fn update_spores(dt: f32, spores: &mut agent::AgentMap, touched: &MateMap, dormancy: &Dormancy,
+	                 awake: &HashSet<obj::Id>)
+	                 -> (Box<[(geometry::Transform, f32, gen::Dna)]>, Box<[(geometry::Transform, gen::Dna)]>) {
 		let mut spawns = Vec::new();
+		let mut rotten = Vec::new();
 		for (spore_id, spore) in spores.iter_mut() {
 			if spore.state.lifecycle().is_expired() {
-				spore.state.die();
-				spawns.push((spore.transform().clone(), Self::crossover(spore.dna(), spore.state.foreign_dna())))
+				if !dormancy.enabled || awake.contains(spore_id) {
+					spore.state.die();
+					let energy = spore.state.energy();
+					spawns.push((spore.transform().clone(),
+					             spore.state.drain(energy),
+					             Self::crossover(spore.dna(), spore.state.foreign_dna())))
+				} else if spore.state.lifecycle().overtime() > dormancy.max_dormancy {
+					spore.state.die();
+					rotten.push((spore.transform().clone(), spore.dna().clone()));
+				}
 			} else if spore.state.is_active() {
-				for segment in spore.segments.iter_mut() {
-					if let Some(key) = segment.state.last_touched {
-						if let Some(touched_dna) = touched.get(&key.id()) {
-							info!("fertilised: {} by {} as {}",
-							      spore_id,
-							      key.id(),
-							      touched_dna.to_base64(base64::STANDARD));
-							spore.state.fertilise(touched_dna);
-						}
-					}
+				if let Some(&(mate_id, ref touched_dna)) = touched.get(spore_id) {
+					info!("fertilised: {} by {} as {}",
+					      spore_id,
+					      mate_id,
+					      touched_dna.to_base64(base64::STANDARD));
+					spore.state.fertilise(mate_id, touched_dna);
 				}
 				for segment in spore.segments.iter_mut() {
 					segment.state.update(dt)
 				}
 			}
 		}
-		spawns.into_boxed_slice()
+		(spawns.into_boxed_slice(), rotten.into_boxed_slice())
 	}
 }
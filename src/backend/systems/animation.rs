@@ -4,8 +4,8 @@ use core::clock::*;
 
 pub struct AnimationSystem {
 	speed: f32,
-	t0: SystemStopwatch,
-	now: SystemStopwatch,
+	t0: SimulationStopwatch,
+	now: SimulationStopwatch,
 	dt: f32,
 	frames: f32,
 	elapsed: f32,
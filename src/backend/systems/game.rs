@@ -15,7 +15,7 @@ pub struct GameSystem {
 
 struct Emitter {
 	position: Position,
-	hourglass: Hourglass<SystemStopwatch>,
+	hourglass: Hourglass<SimulationStopwatch>,
 	to_spawn: usize,
 	spawned: usize,
 	emission: Emission,
@@ -73,16 +73,22 @@ impl System for GameSystem {
 					Emission::CCW(angle) => angle * i as f32,
 					Emission::CW(angle) => -angle * i as f32,
 				};
+				// resources leave with the current
+				let current = world.flow().velocity_at(&e.position);
 				world.new_resource(&Transform::new(e.position, r),
 				                   Some(&Motion {
-					                   velocity: Velocity::new(r.cos(), r.sin()) * e.velocity,
+					                   velocity: Velocity::new(r.cos(), r.sin()) * e.velocity + current,
 					                   spin: e.spin,
 				                   }));
 			}
 		}
+		// keep every swarm within its caps
+		for t in agent::AgentType::all() {
+			world.swarm_mut(t).cull();
+		}
 		// if there are no minions, spawn some
 		if world.agents(agent::AgentType::Minion).is_empty() {
-			world.init_minions();
+			world.recover();
 		}
 	}
 }
@@ -4,6 +4,7 @@ pub mod ai;
 pub mod alife;
 pub mod game;
 pub mod audio;
+pub mod scent;
 
 pub use self::physics::PhysicsSystem;
 pub use self::animation::AnimationSystem;
@@ -11,6 +12,7 @@ pub use self::game::GameSystem;
 pub use self::ai::AiSystem;
 pub use self::alife::AlifeSystem;
 pub use self::audio::AudioSystem;
+pub use self::scent::ScentSystem;
 
 use backend::world;
 
@@ -1,5 +1,4 @@
 use super::*;
-use std::f32::consts;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -8,12 +7,16 @@ use wrapped2d::user_data::*;
 use wrapped2d::dynamics::world::callbacks::ContactAccess;
 use core::geometry::*;
 use cgmath::Vector;
+use cgmath::EuclideanVector;
 use backend::obj;
 use backend::obj::*;
 use backend::world;
 use backend::world::agent;
+use backend::world::agent::TypedAgent;
 use backend::world::segment;
 use backend::world::segment::Intent;
+use backend::world::flow::FlowField;
+use backend::world::obstacle;
 
 struct AgentData;
 
@@ -23,12 +26,125 @@ impl UserDataTypes for AgentData {
 	type FixtureData = agent::Key;
 }
 
-type ContactSet = Rc<RefCell<HashMap<agent::Key, agent::Key>>>;
+type ContactSet = Rc<RefCell<HashMap<agent::Key, Vec<segment::Contact>>>>;
+// joints by the segment they attach, together with the segment they attach it to
+type JointMap = HashMap<agent::Key, (agent::Key, b2::JointHandle)>;
+
+// Quadratic drag of the water on the outline of every body
+#[derive(Clone)]
+pub struct Drag {
+	pub enabled: bool,
+	// across the edges, pushing water out of the way
+	pub normal: f32,
+	// along the edges, skin friction
+	pub tangential: f32,
+}
+
+impl Default for Drag {
+	fn default() -> Self {
+		Drag {
+			enabled: true,
+			normal: 0.5,
+			tangential: 0.02,
+		}
+	}
+}
+
+// How hard the solver works at each step
+#[derive(Clone)]
+pub struct Iterations {
+	pub velocity: i32,
+	pub position: i32,
+}
+
+impl Default for Iterations {
+	fn default() -> Self {
+		Iterations {
+			velocity: 8,
+			position: 3,
+		}
+	}
+}
+
+// uniform damping used when drag is off
+const LINEAR_DAMPING: f32 = 0.8;
+// walls and obstacles get the first category bit after the agent types
+const SCENERY_CATEGORY: u16 = 1 << 8;
+
+// Who bumps into whom. Every agent type has its own category bit
+// and a mask of the types it collides with, the scenery stops everyone
+#[derive(Clone)]
+pub struct Collisions {
+	masks: HashMap<agent::AgentType, u16>,
+}
+
+impl Default for Collisions {
+	fn default() -> Self {
+		use backend::world::agent::AgentType::*;
+		let all = agent::AgentType::all().iter().fold(0, |a, &t| a | Self::category(t));
+		Collisions { masks: agent::AgentType::all().iter().map(|&t| (t, all)).collect() }
+			.with(Spore, Resource, false)
+			.with(FriendlyBullet, Player, false)
+			.with(FriendlyBullet, FriendlyBullet, false)
+			.with(EnemyBullet, Enemy, false)
+			.with(EnemyBullet, EnemyBullet, false)
+	}
+}
+
+impl Collisions {
+	pub fn category(agent_type: agent::AgentType) -> u16 {
+		1 << agent_type as u16
+	}
+
+	// both ways, or it would take only one to collide
+	pub fn with(mut self, a: agent::AgentType, b: agent::AgentType, collide: bool) -> Self {
+		for &(t, other) in &[(a, b), (b, a)] {
+			let mask = self.masks.entry(t).or_insert(0);
+			if collide {
+				*mask |= Self::category(other);
+			} else {
+				*mask &= !Self::category(other);
+			}
+		}
+		self
+	}
+
+	fn mask(&self, agent_type: agent::AgentType) -> u16 {
+		self.masks.get(&agent_type).map(|m| *m).unwrap_or(0) | SCENERY_CATEGORY
+	}
+
+	fn filter(&self, agent_type: agent::AgentType) -> b2::Filter {
+		b2::Filter {
+			category_bits: Self::category(agent_type),
+			mask_bits: self.mask(agent_type),
+			group_index: 0,
+		}
+	}
+
+	// walls and obstacles stand in the way of everything
+	fn scenery_filter() -> b2::Filter {
+		b2::Filter {
+			category_bits: SCENERY_CATEGORY,
+			mask_bits: 0xFFFF,
+			group_index: 0,
+		}
+	}
+}
 
 pub struct PhysicsSystem {
 	world: b2::World<AgentData>,
 	handles: HashMap<agent::Key, b2::BodyHandle>,
-	touched: ContactSet,
+	joints: JointMap,
+	drag: Drag,
+	collisions: Collisions,
+	iterations: Iterations,
+	flow: FlowField,
+	obstacles: usize,
+	extent: Rect,
+	topology: world::Topology,
+	walls: Option<b2::BodyHandle>,
+	dt: f32,
+	contacts: ContactSet,
 }
 
 impl Updateable for PhysicsSystem {
@@ -53,6 +169,39 @@ impl Updateable for PhysicsSystem {
 					Intent::RunAway(impulse) => impulses.push((h, center, impulse * dt)),
 					_ => {}
 				}
+				if self.drag.enabled {
+					let c = PhysicsSystem::from_vec2((*body).position());
+					let radius = segment.mesh.shape.radius();
+					let vertices = &segment.mesh.vertices;
+					let n = vertices.len();
+					for i in 0..n {
+						let p0 = PhysicsSystem::from_vec2(&(*body).world_point(&PhysicsSystem::vec2(&vertices[i], radius)));
+						let p1 = PhysicsSystem::from_vec2(&(*body)
+							.world_point(&PhysicsSystem::vec2(&vertices[(i + 1) % n], radius)));
+						let edge = p1 - p0;
+						let length = edge.length();
+						if length <= 0. {
+							continue;
+						}
+						let tangent = edge / length;
+						let mid = (p0 + p1) * 0.5;
+						let outward = Position::new(tangent.y, -tangent.x);
+						let normal = if outward.dot(mid - c) < 0. { outward * -1. } else { outward };
+						// what matters is how fast the edge moves through the water, not over the ground
+						let v = PhysicsSystem::from_vec2(&(*body)
+							.linear_velocity_from_world_point(&PhysicsSystem::to_vec2(&mid))) -
+						        self.flow.velocity_at(&mid);
+						let (vn, vt) = (v.dot(normal), v.dot(tangent));
+						// only the side moving into the water gets pushed back
+						let pressure = if vn > 0. { -self.drag.normal * vn * vn } else { 0. };
+						let friction = -self.drag.tangential * vt * vt.abs();
+						forces.push((h, PhysicsSystem::to_vec2(&mid), (normal * pressure + tangent * friction) * length));
+					}
+				} else if self.flow.enabled {
+					// damping pulls bodies towards the speed of the current rather than to a halt
+					let flow = self.flow.velocity_at(&PhysicsSystem::from_vec2(&center));
+					forces.push((h, center, flow * (*body).mass() * LINEAR_DAMPING));
+				}
 			}
 		}
 		for (h, center, force) in forces {
@@ -64,7 +213,24 @@ impl Updateable for PhysicsSystem {
 			let b = &mut self.world.body_mut(h);
 			b.apply_linear_impulse(&PhysicsSystem::to_vec2(&impulse), &center, true);
 		}
-		self.world.step(dt, 8, 3);
+
+		for (key, &(_, h)) in self.joints.iter() {
+			if let Some(segment) = state.agent(key.agent_id).and_then(|c| c.segment(key.segment_index)) {
+				if segment.flags.contains(segment::MOTOR) {
+					let mut joint = self.world.joint_mut(h);
+					if let b2::UnknownJoint::Revolute(ref mut revolute) = **joint {
+						revolute.enable_motor(true);
+						revolute.set_motor_speed(segment.state.motor_speed);
+						revolute.set_max_motor_torque(segment.state.motor_torque);
+					}
+				}
+			}
+		}
+		self.dt = dt;
+		self.world.step(dt, self.iterations.velocity, self.iterations.position);
+		if self.topology == world::Topology::Toroidal {
+			self.wrap_bodies();
+		}
 	}
 }
 
@@ -79,9 +245,13 @@ struct JointRef<'a> {
 impl System for PhysicsSystem {
 	fn register(&mut self, agent: &world::agent::Agent) {
 		// build fixtures
-		let joint_refs = PhysicsSystem::build_fixtures(&mut self.world, &agent);
+		let linear_damping = if self.drag.enabled { 0. } else { LINEAR_DAMPING };
+		let filter = self.collisions.filter(agent.id().type_of());
+		let joint_refs = PhysicsSystem::build_fixtures(&mut self.world, &agent, linear_damping, &filter);
 		// and then assemble them with joints
-		PhysicsSystem::build_joints(&mut self.world, &joint_refs);
+		for (key, joint) in PhysicsSystem::build_joints(&mut self.world, &joint_refs) {
+			self.joints.insert(key, joint);
+		}
 		// record them
 		for JointRef { refs, handle, .. } in joint_refs {
 			self.handles.insert(refs, handle);
@@ -92,18 +262,40 @@ impl System for PhysicsSystem {
 		let object_id = agent.id();
 		let segments = agent.segments();
 		for segment in segments {
-			let refs = agent::Key::with_segment(object_id, segment.index);
-			if let Some(handle) = self.handles.remove(&refs) {
-				self.world.destroy_body(handle);
-			}
+			self.destroy_body(&agent::Key::with_segment(object_id, segment.index));
 		}
 	}
 
 	fn init(&mut self, world: &world::World) {
-		self.init_extent(&world.extent);
+		self.extent = world.extent;
+		self.topology = world.topology();
+		if self.topology == world::Topology::Bounded {
+			self.walls = Some(self.init_extent(&world.extent));
+		}
+	}
+
+	fn from_world(&mut self, world: &world::World) {
+		self.flow = world.flow().clone();
+		if world.topology() != self.topology {
+			self.set_topology(world.topology());
+		}
+		// add missing obstacles - deletion not supported
+		for o in &world.obstacles()[self.obstacles..] {
+			self.add_obstacle(o);
+		}
+		self.obstacles = world.obstacles().len();
+		// severed segments leave the physics world, their joints go with the body
+		for (_, swarm) in world.swarms().iter() {
+			for (&id, agent) in swarm.agents().iter() {
+				for segment in agent.segments().iter().filter(|s| s.state.is_severed()) {
+					self.destroy_body(&agent::Key::with_segment(id, segment.index));
+				}
+			}
+		}
 	}
 
 	fn to_world(&self, world: &mut world::World) {
+		world.flow_mut().advance(self.dt);
 		for (_, b) in self.world.bodies() {
 			let body = b.borrow();
 			let position = (*body).position();
@@ -113,21 +305,50 @@ impl System for PhysicsSystem {
 			if let Some(agent) = world.agent_mut(key.agent_id) {
 				if let Some(segment) = agent.segment_mut(key.segment_index) {
 					segment.transform_to(&Transform::new(PhysicsSystem::from_vec2(&position), angle));
-					segment.state.last_touched = self.touched.borrow().get(key).map(|r| *r);
+					segment.state.set_contacts(self.contacts.borrow().get(key).map(|c| c.as_slice()).unwrap_or(&[]));
 				}
 			}
 		}
-		self.touched.borrow_mut().clear();
+		self.contacts.borrow_mut().clear();
+
+		for (key, &(_, h)) in self.joints.iter() {
+			let joint = self.world.joint(h);
+			if let b2::UnknownJoint::Revolute(ref revolute) = **joint {
+				if revolute.is_motor_enabled() {
+					let segment = world.agent_mut(key.agent_id).and_then(|a| a.segment_mut(key.segment_index));
+					if let Some(segment) = segment {
+						segment.state.motor_work = (revolute.motor_torque() * revolute.joint_speed()).abs() * self.dt;
+					}
+				}
+			}
+		}
+
+		for (_, agent) in world.agents_mut(agent::AgentType::Minion).iter_mut() {
+			if let Some(sensor) = agent.first_segment(segment::SENSOR) {
+				let sight = self.look(agent.id(), sensor.transform(), agent.vision());
+				agent.state.see(sight);
+			}
+		}
 	}
 }
 
 impl Default for PhysicsSystem {
 	fn default() -> Self {
-		let touched = Rc::new(RefCell::new(HashMap::new()));
+		let contacts = Rc::new(RefCell::new(HashMap::new()));
 		PhysicsSystem {
-			world: Self::new_world(touched.clone()),
+			world: Self::new_world(contacts.clone()),
 			handles: HashMap::new(),
-			touched: touched,
+			joints: HashMap::new(),
+			drag: Drag::default(),
+			collisions: Collisions::default(),
+			iterations: Iterations::default(),
+			flow: FlowField::default(),
+			obstacles: 0,
+			extent: Rect::new(-1., -1., 1., 1.),
+			topology: world::Topology::Bounded,
+			walls: None,
+			dt: 1. / 60.,
+			contacts: contacts,
 		}
 	}
 }
@@ -141,8 +362,108 @@ impl PhysicsSystem {
 		Position::new(p.x, p.y)
 	}
 
-	fn init_extent(&mut self, extent: &Rect) {
+	// drag takes the place of uniform linear damping
+	pub fn toggle_drag(&mut self) {
+		self.drag.enabled = !self.drag.enabled;
+		self.update_linear_damping();
+		info!("drag: {}", self.drag.enabled);
+	}
+
+	pub fn set_drag(&mut self, drag: Drag) {
+		self.drag = drag;
+		self.update_linear_damping();
+	}
+
+	fn update_linear_damping(&mut self) {
+		let linear_damping = if self.drag.enabled { 0. } else { LINEAR_DAMPING };
+		for (_, b) in self.world.bodies() {
+			b.borrow_mut().set_linear_damping(linear_damping);
+		}
+	}
+
+	pub fn set_iterations(&mut self, iterations: Iterations) {
+		self.iterations = iterations;
+	}
+
+	// bodies already in the world are refiltered on the spot
+	pub fn set_collisions(&mut self, collisions: Collisions) {
+		self.collisions = collisions;
+		for (_, b) in self.world.bodies() {
+			let body = b.borrow();
+			let filter = if (*body).body_type() == b2::BodyType::Static {
+				Collisions::scenery_filter()
+			} else {
+				self.collisions.filter((*body).user_data().id().type_of())
+			};
+			for (_, f) in (*body).fixtures() {
+				f.borrow_mut().set_filter_data(&filter);
+			}
+		}
+	}
+
+	// box2d takes the joints along with the body
+	fn destroy_body(&mut self, key: &agent::Key) {
+		if let Some(handle) = self.handles.remove(key) {
+			self.world.destroy_body(handle);
+			let gone = self.joints
+				.iter()
+				.filter(|&(distal, &(ref medial, _))| distal == key || medial == key)
+				.map(|(distal, _)| *distal)
+				.collect::<Vec<_>>();
+			for distal in gone {
+				self.joints.remove(&distal);
+			}
+		}
+	}
+
+	// walls come down on a torus, and go back up when bounded
+	fn set_topology(&mut self, topology: world::Topology) {
+		self.topology = topology;
+		match topology {
+			world::Topology::Toroidal => {
+				if let Some(handle) = self.walls.take() {
+					self.world.destroy_body(handle);
+				}
+			}
+			world::Topology::Bounded => {
+				if self.walls.is_none() {
+					let extent = self.extent;
+					self.walls = Some(self.init_extent(&extent));
+				}
+			}
+		}
+	}
+
+	// whatever leaves from one edge comes back from the opposite one, all segments together,
+	// moved by where the first segment ended up
+	fn wrap_bodies(&mut self) {
+		let mut shifts = HashMap::new();
+		for (_, b) in self.world.bodies() {
+			let body = b.borrow();
+			let key = (*body).user_data();
+			let p = Self::from_vec2((*body).position());
+			if (*body).body_type() == b2::BodyType::Dynamic && key.segment_index == 0 && !self.extent.contains(&p) {
+				shifts.insert(key.agent_id, self.extent.wrap(&p) - p);
+			}
+		}
+		if shifts.is_empty() {
+			return;
+		}
+		let mut moves = Vec::new();
+		for (h, b) in self.world.bodies() {
+			let body = b.borrow();
+			if let Some(shift) = shifts.get(&(*body).user_data().agent_id) {
+				moves.push((h, Self::from_vec2((*body).position()) + *shift, (*body).angle()));
+			}
+		}
+		for (h, position, angle) in moves {
+			self.world.body_mut(h).set_transform(&Self::to_vec2(&position), angle);
+		}
+	}
+
+	fn init_extent(&mut self, extent: &Rect) -> b2::BodyHandle {
 		let mut f_def = b2::FixtureDef::new();
+		f_def.filter = Collisions::scenery_filter();
 		let mut b_def = b2::BodyDef::new();
 		b_def.body_type = b2::BodyType::Static;
 		let refs = agent::Key::with_id(0xFFFFFFFFusize);
@@ -155,6 +476,37 @@ impl PhysicsSystem {
 		                   Self::to_vec2(&extent.top_left())]);
 
 		self.world.body_mut(handle).create_fixture_with(&rect, &mut f_def, refs);
+		handle
+	}
+
+	// static bodies keyed by the obstacle id, so whatever bumps into them knows it hit a prop
+	fn add_obstacle(&mut self, obstacle: &obstacle::Obstacle) {
+		let mut f_def = b2::FixtureDef::new();
+		f_def.filter = Collisions::scenery_filter();
+		let mut b_def = b2::BodyDef::new();
+		b_def.body_type = b2::BodyType::Static;
+		b_def.position = Self::to_vec2(&obstacle.transform().position);
+		b_def.angle = obstacle.transform().angle;
+		let refs = agent::Key::with_id(obstacle.id());
+		let handle = self.world.create_body_with(&b_def, refs);
+		let mut body = self.world.body_mut(handle);
+		match obstacle.outline {
+			obstacle::Outline::Circle { radius } => {
+				let mut circle = b2::CircleShape::new();
+				circle.set_radius(radius);
+				body.create_fixture_with(&circle, &mut f_def, refs);
+			}
+			obstacle::Outline::Polygon { ref vertices } => {
+				let mut poly = b2::PolygonShape::new();
+				poly.set(&vertices.iter().map(|p| Self::to_vec2(p)).collect::<Vec<_>>());
+				body.create_fixture_with(&poly, &mut f_def, refs);
+			}
+			obstacle::Outline::Chain { ref vertices } => {
+				let mut chain = b2::ChainShape::new();
+				chain.create_chain(&vertices.iter().map(|p| Self::to_vec2(p)).collect::<Vec<_>>());
+				body.create_fixture_with(&chain, &mut f_def, refs);
+			}
+		}
 	}
 
 	fn vec2(p: &Position, radius: f32) -> b2::Vec2 {
@@ -164,7 +516,9 @@ impl PhysicsSystem {
 		}
 	}
 
-	fn build_fixtures<'a>(world: &mut b2::World<AgentData>, agent: &'a world::agent::Agent) -> Vec<JointRef<'a>> {
+	fn build_fixtures<'a>(world: &mut b2::World<AgentData>, agent: &'a world::agent::Agent, linear_damping: f32,
+	                      filter: &b2::Filter)
+	                      -> Vec<JointRef<'a>> {
 		let object_id = agent.id();
 		let segments = agent.segments();
 		segments.into_iter()
@@ -175,12 +529,13 @@ impl PhysicsSystem {
 				f_def.density = material.density;
 				f_def.restitution = material.restitution;
 				f_def.friction = material.friction;
+				f_def.filter = filter.clone();
 
 				let transform = segment.transform();
 				let mut b_def = b2::BodyDef::new();
 				b_def.body_type = b2::BodyType::Dynamic;
-				b_def.linear_damping = 0.8;
-				b_def.angular_damping = 0.9;
+				b_def.linear_damping = linear_damping;
+				b_def.angular_damping = material.angular_damping;
 				b_def.angle = transform.angle;
 				b_def.position = Self::vec2(&transform.position, 1.);
 				if let Some(Motion { velocity, spin }) = segment.motion {
@@ -259,8 +614,9 @@ impl PhysicsSystem {
 			.collect::<Vec<_>>()
 	}
 
-	fn build_joints(world: &mut b2::World<AgentData>, joint_refs: &Vec<JointRef>) {
-		for &JointRef { handle: distal, mesh, attachment, flags, .. } in joint_refs {
+	fn build_joints(world: &mut b2::World<AgentData>, joint_refs: &Vec<JointRef>) -> JointMap {
+		let mut joints = HashMap::new();
+		for &JointRef { refs, handle: distal, mesh, attachment, flags } in joint_refs {
 			if let Some(attachment) = attachment {
 				let upstream = &joint_refs[attachment.index as usize];
 				let medial = upstream.handle;
@@ -271,36 +627,81 @@ impl PhysicsSystem {
 				let a = b2::Vec2 { x: v0.x, y: v0.y };
 				let b = b2::Vec2 { x: v1.x, y: v1.y };
 				macro_rules! common_joint (
-					($joint:ident) => {
+					($joint:ident) => {{
 						$joint.collide_connected = false;
 						$joint.reference_angle = angle_delta;
 						$joint.local_anchor_a = a;
 						$joint.local_anchor_b = b;
 						world.create_joint_with(&$joint, ())
-					}
+					}}
 				);
-				if flags.contains(world::segment::JOINT) {
-					let mut joint = b2::RevoluteJointDef::new(medial, distal);
-					joint.enable_limit = true;
-					joint.upper_angle = consts::PI / 6.;
-					joint.lower_angle = -consts::PI / 6.;
-					common_joint!(joint);
-				} else {
-					let mut joint = b2::WeldJointDef::new(medial, distal);
-					joint.frequency = 5.0;
-					joint.damping_ratio = 0.9;
-					common_joint!(joint);
-				}
+				let handle = match attachment.joint {
+					segment::Joint::Revolute { lower, upper } => {
+						let mut joint = b2::RevoluteJointDef::new(medial, distal);
+						joint.enable_limit = true;
+						joint.lower_angle = lower;
+						joint.upper_angle = upper;
+						joint.enable_motor = flags.contains(segment::MOTOR);
+						common_joint!(joint)
+					}
+					segment::Joint::Weld { frequency, damping_ratio } => {
+						let mut joint = b2::WeldJointDef::new(medial, distal);
+						joint.frequency = frequency;
+						joint.damping_ratio = damping_ratio;
+						common_joint!(joint)
+					}
+				};
+				joints.insert(refs, (upstream.refs, handle));
 			}
 		}
+		joints
 	}
 
-	fn new_world(touched: ContactSet) -> b2::World<AgentData> {
+	fn new_world(contacts: ContactSet) -> b2::World<AgentData> {
 		let mut world = b2::World::new(&b2::Vec2 { x: 0.0, y: -0.5 });
-		world.set_contact_listener(Box::new(ContactListener { touched: touched }));
+		world.set_contact_listener(Box::new(ContactListener { contacts: contacts }));
 		world
 	}
 
+	// casts the rays of the vision cone and reports the closest fixture hit by each,
+	// ignoring the fixtures of the agent doing the looking
+	fn look(&self, agent_id: Id, transform: &Transform, vision: &agent::Vision) -> Box<[agent::RayHit]> {
+		let p0 = Self::to_vec2(&transform.position);
+		(0..vision.rays)
+			.map(|i| {
+				// the sensor points towards its local -y axis
+				let angle = transform.angle + vision.ray_angle(i);
+				let p1 = b2::Vec2 {
+					x: p0.x + angle.sin() * vision.range,
+					y: p0.y - angle.cos() * vision.range,
+				};
+				let mut hit = agent::RayHit {
+					distance: vision.range,
+					agent_type: None,
+				};
+				{
+					let mut callback = |body_h: b2::BodyHandle, fixture_h: b2::FixtureHandle, _: &b2::Vec2,
+					                    _: &b2::Vec2, fraction: f32| {
+						let body = self.world.body(body_h);
+						let key = *body.fixture(fixture_h).user_data();
+						if key.agent_id == agent_id {
+							-1.
+						} else {
+							hit = agent::RayHit {
+								distance: fraction * vision.range,
+								agent_type: Some(key.agent_id.type_of()),
+							};
+							fraction
+						}
+					};
+					self.world.ray_cast(&mut callback, &p0, &p1);
+				}
+				hit
+			})
+			.collect::<Vec<_>>()
+			.into_boxed_slice()
+	}
+
 	pub fn pick(&self, pos: Position) -> Option<Id> {
 		let point = Self::to_vec2(&pos);
 		let eps = 0.001f32;
@@ -332,17 +733,46 @@ impl PhysicsSystem {
 	}
 }
 
+// collects every contact of the step for both sides
 struct ContactListener {
-	touched: ContactSet,
+	contacts: ContactSet,
 }
 
 impl b2::ContactListener<AgentData> for ContactListener {
-	fn post_solve(&mut self, ca: ContactAccess<AgentData>, _: &b2::ContactImpulse) {
-		let body_a = ca.fixture_a.user_data();
-		let body_b = ca.fixture_b.user_data();
-		if body_a.agent_id != body_b.agent_id {
-			self.touched.borrow_mut().insert(body_a.no_bone(), body_b.no_bone());
-			self.touched.borrow_mut().insert(body_b.no_bone(), body_a.no_bone());
+	fn post_solve(&mut self, ca: ContactAccess<AgentData>, impulse: &b2::ContactImpulse) {
+		let key_a = ca.fixture_a.user_data().no_bone();
+		let key_b = ca.fixture_b.user_data().no_bone();
+		if key_a.agent_id != key_b.agent_id {
+			let count = impulse.count as usize;
+			let magnitude = impulse.normal_impulses[..count]
+				.iter()
+				.fold(0f32, |a, i| a.max(i.abs()));
+			let mut contacts = self.contacts.borrow_mut();
+			Self::record(contacts.entry(key_a).or_insert_with(Vec::new),
+			             segment::Contact {
+				             other: key_b,
+				             impulse: magnitude,
+			             });
+			Self::record(contacts.entry(key_b).or_insert_with(Vec::new),
+			             segment::Contact {
+				             other: key_a,
+				             impulse: magnitude,
+			             });
+		}
+	}
+}
+
+impl ContactListener {
+	// stars have a fixture per bone, so the same pair can touch in several places at once:
+	// they count as one contact, with the hardest push
+	fn record(contacts: &mut Vec<segment::Contact>, contact: segment::Contact) {
+		match contacts.iter_mut().find(|c| c.other == contact.other) {
+			Some(existing) => {
+				if contact.impulse > existing.impulse {
+					*existing = contact;
+				}
+			}
+			None => contacts.push(contact),
 		}
 	}
 }
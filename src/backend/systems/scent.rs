@@ -0,0 +1,53 @@
+use super::*;
+use backend::obj::Transformable;
+use backend::world;
+use backend::world::agent;
+use backend::world::scent::Channel;
+use backend::world::WorldState;
+use core::geometry::Position;
+
+// scent deposited per second by each active agent
+const FOOD_RATE: f32 = 1.;
+const MINION_RATE: f32 = 1.;
+
+pub struct ScentSystem {
+	dt: f32,
+	sources: Vec<(Channel, Position, f32)>,
+}
+
+impl Updateable for ScentSystem {
+	fn update(&mut self, _: &WorldState, dt: f32) {
+		self.dt = dt;
+	}
+}
+
+impl System for ScentSystem {
+	fn from_world(&mut self, world: &world::World) {
+		self.sources.clear();
+		for (_, resource) in world.agents(agent::AgentType::Resource).iter().filter(|&(_, a)| a.state.is_active()) {
+			self.sources.push((Channel::Food, resource.transform().position, FOOD_RATE));
+		}
+		for (_, minion) in world.agents(agent::AgentType::Minion).iter().filter(|&(_, a)| a.state.is_active()) {
+			self.sources.push((Channel::Minion, minion.transform().position, MINION_RATE));
+		}
+	}
+
+	fn to_world(&self, world: &mut world::World) {
+		let scent = world.scent_mut();
+		for &(channel, ref position, rate) in self.sources.iter() {
+			scent.deposit(channel, position, rate * self.dt);
+		}
+		scent.update(self.dt);
+	}
+}
+
+impl Default for ScentSystem {
+	fn default() -> Self {
+		ScentSystem {
+			dt: 1. / 60.,
+			sources: Vec::new(),
+		}
+	}
+}
+
+impl ScentSystem {}
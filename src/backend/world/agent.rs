@@ -133,11 +133,14 @@ pub struct GBrain<T: Copy + Default> {
 	pub haste: T,
 	pub prudence: T,
 	pub fear: T,
+	pub aggression: T,
 	pub rest: T,
 	pub thrust: T,
 	pub weights_in: WeightMatrix<T>,
 	pub weights_hidden: WeightMatrix<T>,
 	pub weights_out: WeightMatrix<T>,
+	// from the food and minion scent gradients, each ahead and to the side, into the first layer
+	pub weights_scent: WeightMatrix<T>,
 }
 
 pub trait TypedBrain {
@@ -153,9 +156,10 @@ pub trait Personality<S>
 	fn haste(&self) -> S;
 	fn prudence(&self) -> S;
 	fn fear(&self) -> S;
+	fn aggression(&self) -> S;
 	fn rest(&self) -> S;
 	fn thrust(&self) -> S;
-	fn response(&self, input: &InputVector<S>) -> OutputVector<S>;
+	fn response(&self, input: &InputVector<S>, scent: &InputVector<S>) -> OutputVector<S>;
 }
 
 pub trait Layer<S, T>
@@ -176,6 +180,22 @@ pub trait Layer<S, T>
 		}
 		outputs
 	}
+
+	// two sets of inputs into the same neurons
+	fn merged_layer(a: &[S], weights_a: &[WeightVector<T>], b: &[S], weights_b: &[WeightVector<T>])
+	                -> OutputVector<S> {
+		let mut outputs = [S::zero(); N_WEIGHTS];
+		for i in 0..outputs.len() {
+			for j in 0..a.len() {
+				outputs[i] = outputs[i] + a[j] * weights_a[i][j].into();
+			}
+			for j in 0..b.len() {
+				outputs[i] = outputs[i] + b[j] * weights_b[i][j].into();
+			}
+			outputs[i] = Self::activation(outputs[i])
+		}
+		outputs
+	}
 }
 
 impl<S, T> Layer<S, T> for GBrain<T>
@@ -200,6 +220,9 @@ impl<T, S> Personality<S> for GBrain<T>
 	fn fear(&self) -> S {
 		self.fear.into()
 	}
+	fn aggression(&self) -> S {
+		self.aggression.into()
+	}
 	fn rest(&self) -> S {
 		self.rest.into()
 	}
@@ -207,8 +230,8 @@ impl<T, S> Personality<S> for GBrain<T>
 		self.thrust.into()
 	}
 
-	fn response(&self, input: &InputVector<S>) -> OutputVector<S> {
-		let output_in = Self::layer(input, &self.weights_in);
+	fn response(&self, input: &InputVector<S>, scent: &InputVector<S>) -> OutputVector<S> {
+		let output_in = Self::merged_layer(input, &self.weights_in, scent, &self.weights_scent);
 		let output_hidden = Self::layer(&output_in, &self.weights_hidden);
 		let output_out = Self::layer(&output_hidden, &self.weights_out);
 		output_out
@@ -225,6 +248,106 @@ impl<T> TypedBrain for GBrain<T>
 
 pub type Brain = GBrain<f32>;
 
+impl Brain {
+	// total strength of all the connections, what it takes to keep the brain running
+	pub fn synapses(&self) -> f32 {
+		let sum = |m: &WeightMatrix<f32>| m.iter().fold(0., |a, row| row.iter().fold(a, |a, w| a + w.abs()));
+		sum(&self.weights_in) + sum(&self.weights_hidden) + sum(&self.weights_out) + sum(&self.weights_scent)
+	}
+}
+
+// field of view of the sensor segment, sampled by evenly spaced ray casts
+#[derive(Clone,Debug)]
+pub struct Vision {
+	pub fov: Angle,
+	pub rays: usize,
+	pub range: f32,
+}
+
+impl Default for Vision {
+	fn default() -> Self {
+		Vision {
+			fov: 0.,
+			rays: 0,
+			range: 0.,
+		}
+	}
+}
+
+impl Vision {
+	// angle of the i-th ray relative to the direction the sensor is pointing
+	pub fn ray_angle(&self, i: usize) -> Angle {
+		if self.rays > 1 {
+			self.fov * (i as f32 / (self.rays - 1) as f32 - 0.5)
+		} else {
+			0.
+		}
+	}
+}
+
+// when and how a minion spends energy on offspring
+#[derive(Clone,Debug)]
+pub struct Reproduction {
+	// seconds between clutches
+	pub interval: f32,
+	// fraction of the maximum energy put into a clutch
+	pub investment: f32,
+	// number of spores the investment is shared between
+	pub clutch: usize,
+	// speed at which spores are launched
+	pub dispersal: f32,
+	// minimum age before the first clutch
+	pub maturity: f32,
+	// fraction of the maximum energy spent fertilising a spore
+	pub mating_effort: f32,
+}
+
+impl Default for Reproduction {
+	fn default() -> Self {
+		Reproduction {
+			interval: 5.,
+			investment: 0.75,
+			clutch: 1,
+			dispersal: 0.,
+			maturity: 0.,
+			mating_effort: 0.,
+		}
+	}
+}
+
+// what a spore looks for in a mate before accepting its genes
+#[derive(Clone,Debug)]
+pub struct Preference {
+	pub tint: f32,
+	// how far on the colour wheel the mate's tint can be from the preferred one
+	pub tolerance: f32,
+	pub min_size: f32,
+}
+
+impl Default for Preference {
+	fn default() -> Self {
+		Preference {
+			tint: 0.,
+			tolerance: 0.5,
+			min_size: 0.,
+		}
+	}
+}
+
+impl Preference {
+	pub fn accepts(&self, mate: &Agent) -> bool {
+		let d = (self.tint - mate.tint()).abs();
+		d.min(1. - d) <= self.tolerance && mate.size() >= self.min_size
+	}
+}
+
+// what the first fixture along a ray is, and how far
+#[derive(Clone,Copy,Debug)]
+pub struct RayHit {
+	pub distance: f32,
+	pub agent_type: Option<AgentType>,
+}
+
 
 bitflags! {
 	pub flags Flags: u32 {
@@ -237,23 +360,29 @@ bitflags! {
 #[derive(Clone,Debug)]
 pub struct Limits {
 	max_energy: f32,
+	max_age: f32,
 }
 
 #[derive(Clone,Debug)]
 pub struct State {
-	lifecycle: Hourglass<SystemStopwatch>,
+	lifecycle: Hourglass<SimulationStopwatch>,
 	flags: Flags,
+	age: f32,
 	energy: f32,
 	target: Option<Id>,
 	target_position: Position,
 	limits: Limits,
 	foreign_dna: Option<Dna>,
+	parents: Vec<Id>,
+	offspring: usize,
+	gathered: f32,
 	trajectory: util::History<Position>,
+	sight: Box<[RayHit]>,
 }
 
 impl State {
 	#[inline]
-	pub fn lifecycle(&self) -> &Hourglass<SystemStopwatch> {
+	pub fn lifecycle(&self) -> &Hourglass<SimulationStopwatch> {
 		&self.lifecycle
 	}
 
@@ -261,10 +390,36 @@ impl State {
 		self.lifecycle.renew()
 	}
 
+	pub fn age(&self) -> f32 {
+		self.age
+	}
+
+	pub fn grow_older(&mut self, dt: f32) {
+		self.age += dt;
+	}
+
+	pub fn is_too_old(&self) -> bool {
+		self.age >= self.limits.max_age
+	}
+
+	// 0 in the prime of life, rising to 1 at the maximum age
+	pub fn senescence(&self) -> f32 {
+		const ONSET: f32 = 0.6;
+		if self.limits.max_age.is_infinite() {
+			return 0.;
+		}
+		let onset = self.limits.max_age * ONSET;
+		((self.age - onset) / (self.limits.max_age - onset)).max(0.).min(1.)
+	}
+
 	pub fn energy(&self) -> f32 {
 		self.energy
 	}
 
+	pub fn max_energy(&self) -> f32 {
+		self.limits.max_energy
+	}
+
 	pub fn energy_ratio(&self) -> f32 {
 		self.energy / self.limits.max_energy
 	}
@@ -283,16 +438,60 @@ impl State {
 		self.consume(max * ratio)
 	}
 
-	pub fn absorb(&mut self, q: f32) {
-		self.energy = self.limits.max_energy.min(self.energy + q);
+	// takes away up to q, returns how much was actually there to take
+	pub fn drain(&mut self, q: f32) -> f32 {
+		let drained = self.energy.min(q);
+		self.energy -= drained;
+		drained
+	}
+
+	// starts over with what it was given, up to its storage. Returns what did not fit
+	pub fn reset_energy(&mut self, energy: f32) -> f32 {
+		self.energy = energy.min(self.limits.max_energy).max(0.);
+		energy - self.energy
+	}
+
+	// a spore is a sealed package, it holds whatever its parent put into it even beyond its storage
+	pub fn provision(&mut self, energy: f32) {
+		self.energy = energy.max(0.);
+	}
+
+	// returns what did not fit
+	pub fn absorb(&mut self, q: f32) -> f32 {
+		let absorbed = q.min(self.limits.max_energy - self.energy).max(0.);
+		self.energy += absorbed;
+		self.gathered += q;
+		q - absorbed
+	}
+
+	// total energy ever taken in
+	pub fn gathered(&self) -> f32 {
+		self.gathered
+	}
+
+	pub fn offspring(&self) -> usize {
+		self.offspring
+	}
+
+	pub fn add_offspring(&mut self, n: usize) {
+		self.offspring += n;
 	}
 
 	pub fn is_fertilised(&self) -> bool {
 		self.foreign_dna.is_some()
 	}
 
-	pub fn fertilise(&mut self, dna: &Dna) {
+	pub fn fertilise(&mut self, mate: Id, dna: &Dna) {
 		self.foreign_dna = Some(dna.clone());
+		self.parents.push(mate);
+	}
+
+	pub fn parents(&self) -> &[Id] {
+		&self.parents
+	}
+
+	pub fn add_parent(&mut self, parent: Id) {
+		self.parents.push(parent);
 	}
 
 	pub fn foreign_dna(&self) -> &Option<Dna> {
@@ -350,13 +549,25 @@ impl State {
 	pub fn trajectory(&self) -> Box<[Position]> {
 		self.trajectory.into_iter().collect::<Vec<_>>().into_boxed_slice()
 	}
+
+	pub fn sight(&self) -> &[RayHit] {
+		&self.sight
+	}
+
+	pub fn see(&mut self, sight: Box<[RayHit]>) {
+		self.sight = sight;
+	}
 }
 
 pub struct Agent {
 	id: Id,
 	brain: Brain,
+	vision: Vision,
+	reproduction: Reproduction,
+	preference: Preference,
 	dna: Dna,
 	gender: u8,
+	tint: f32,
 	pub state: State,
 	pub segments: Box<[Segment]>,
 }
@@ -387,6 +598,19 @@ impl Agent {
 		self.gender
 	}
 
+	#[inline]
+	pub fn tint(&self) -> f32 {
+		self.tint
+	}
+
+	// total area of the body, more or less
+	pub fn size(&self) -> f32 {
+		self.segments
+			.iter()
+			.filter(|s| !s.state.is_severed())
+			.fold(0., |a, s| a + s.mesh.shape.radius().powi(2))
+	}
+
 	#[inline]
 	pub fn segments(&self) -> &[Segment] {
 		&self.segments
@@ -401,8 +625,9 @@ impl Agent {
 		self.segments.get(index as usize)
 	}
 
+	// the last one still attached, falling back to the core
 	pub fn last_segment(&self) -> &Segment {
-		self.segments.last().unwrap()
+		self.segments.iter().rev().find(|s| !s.state.is_severed()).unwrap_or(&self.segments[0])
 	}
 
 	pub fn segment_mut(&mut self, index: SegmentIndex) -> Option<&mut Segment> {
@@ -413,14 +638,28 @@ impl Agent {
 		&self.brain
 	}
 
+	pub fn vision(&self) -> &Vision {
+		&self.vision
+	}
+
+	pub fn reproduction(&self) -> &Reproduction {
+		&self.reproduction
+	}
+
+	pub fn preference(&self) -> &Preference {
+		&self.preference
+	}
+
 	pub fn first_segment(&self, flags: segment::Flags) -> Option<Segment> {
 		self.segments
 			.iter()
-			.find(|segment| segment.flags.contains(flags))
+			.find(|segment| segment.flags.contains(flags) && !segment.state.is_severed())
 			.map(|sensor| sensor.clone())
 	}
 
-	pub fn new(id: Id, gender: u8, brain: &Brain, dna: &Dna, segments: Box<[Segment]>) -> Self {
+	pub fn new(id: Id, gender: u8, tint: f32, brain: &Brain, vision: &Vision, reproduction: &Reproduction,
+	           preference: &Preference, max_age: f32, dna: &Dna, segments: Box<[Segment]>)
+	           -> Self {
 		const SCALE: f32 = 100.;
 		let max_energy = SCALE *
 		                 segments.iter()
@@ -430,16 +669,28 @@ impl Agent {
 			id: id,
 			state: State {
 				flags: ACTIVE,
-				lifecycle: Hourglass::new(5.),
+				lifecycle: Hourglass::new(reproduction.interval),
+				age: 0.,
 				energy: max_energy * 0.5,
 				target: None,
 				target_position: segments[0].transform.position,
-				limits: Limits { max_energy: max_energy },
+				limits: Limits {
+					max_energy: max_energy,
+					max_age: max_age,
+				},
 				foreign_dna: None,
+				parents: Vec::new(),
+				offspring: 0,
+				gathered: 0.,
 				trajectory: util::History::new(600),
+				sight: Box::new([]),
 			},
 			brain: brain.clone(),
+			vision: vision.clone(),
+			reproduction: reproduction.clone(),
+			preference: preference.clone(),
 			gender: gender,
+			tint: tint,
 			dna: dna.clone(),
 			segments: segments,
 		}
@@ -0,0 +1,68 @@
+use core::geometry::*;
+use core::math;
+
+// A whirl of water around a fixed point, positive is counter clockwise
+#[derive(Clone, Debug)]
+pub struct Vortex {
+	pub center: Position,
+	pub strength: f32,
+	// inside this the whirl slows down instead of blowing up
+	pub radius: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Turbulence {
+	pub amplitude: f32,
+	// size of the eddies
+	pub scale: f32,
+	// how fast they change
+	pub rate: f32,
+}
+
+// The water currents, as the sum of a uniform current, some vortices and turbulence
+#[derive(Clone, Debug)]
+pub struct FlowField {
+	pub enabled: bool,
+	pub uniform: Velocity,
+	pub vortices: Vec<Vortex>,
+	pub turbulence: Turbulence,
+	time: f32,
+}
+
+impl Default for FlowField {
+	fn default() -> Self {
+		FlowField {
+			enabled: false,
+			uniform: Velocity::new(0.2, 0.),
+			vortices: Vec::new(),
+			turbulence: Turbulence {
+				amplitude: 0.5,
+				scale: 10.,
+				rate: 0.2,
+			},
+			time: 0.,
+		}
+	}
+}
+
+impl FlowField {
+	pub fn advance(&mut self, dt: f32) {
+		self.time += dt;
+	}
+
+	pub fn velocity_at(&self, p: &Position) -> Velocity {
+		if !self.enabled {
+			return Velocity::new(0., 0.);
+		}
+		let swirl = self.vortices.iter().fold(Velocity::new(0., 0.), |a, vortex| {
+			let d = *p - vortex.center;
+			let d2 = d.x * d.x + d.y * d.y;
+			a + Velocity::new(-d.y, d.x) * (vortex.strength / (d2 + vortex.radius * vortex.radius))
+		});
+		let t = &self.turbulence;
+		let (x, y, z) = (p.x / t.scale, p.y / t.scale, self.time * t.rate);
+		// two samples far apart in the noise make the two components independent
+		let eddy = Velocity::new(math::value_noise(x, y, z), math::value_noise(x + 31.7, y + 17.3, z)) * t.amplitude;
+		self.uniform + swirl + eddy
+	}
+}
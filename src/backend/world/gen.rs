@@ -57,7 +57,10 @@ impl GenePool {
 		self.gene_pool.len()
 	}
 
-	#[allow(dead_code)]
+	pub fn dna(&self) -> &[Dna] {
+		&self.gene_pool
+	}
+
 	pub fn new(gene_pool: &[Dna]) -> Self {
 		GenePool {
 			gene_pool: gene_pool.to_vec().into_boxed_slice(),
@@ -65,6 +68,15 @@ impl GenePool {
 		}
 	}
 
+	// n completely random genomes
+	pub fn random(n: usize) -> Self {
+		let mut rnd = Randomizer::new();
+		GenePool {
+			gene_pool: (0..n).map(|_| rnd.seed().dna().clone()).collect::<Vec<_>>().into_boxed_slice(),
+			round_robin: 0,
+		}
+	}
+
 	pub fn randomize(&mut self) {
 		let mut rnd = Randomizer::new();
 		self.gene_pool[self.round_robin] = rnd.seed().dna().clone();
@@ -0,0 +1,143 @@
+use std::cmp;
+
+pub const MAX_GENDERS: u8 = 4;
+
+// The gender gene is two bits early in the genome, widened by six more decoded last,
+// so that any number of mating types splits the population almost evenly
+pub fn widen(gene: u8, high: u8) -> u8 {
+	(gene & 0x3) | (high << 2)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatingSystem {
+	// a single mating type, anyone can fertilise anyone
+	Hermaphrodite,
+	// male and female
+	TwoSexes,
+	// n self-incompatible mating types, any two different ones are compatible
+	MatingTypes(u8),
+}
+
+// Number of genders and which ones can fertilise which
+#[derive(Clone, Debug)]
+pub struct Genders {
+	count: u8,
+	compatibility: Box<[bool]>,
+}
+
+impl Genders {
+	// compatibility is a count x count row-major matrix, row = spore, column = mate.
+	// The count is clamped to what the gene can express, and missing pairs are incompatible
+	pub fn new(count: u8, compatibility: &[bool]) -> Self {
+		let clamped = cmp::min(cmp::max(count, 1), MAX_GENDERS);
+		let n = clamped as usize * clamped as usize;
+		if clamped != count || compatibility.len() != n {
+			warn!("genders: {} with {} pairs, using {}", count, compatibility.len(), clamped);
+		}
+		Genders {
+			count: clamped,
+			compatibility: (0..n)
+				.map(|i| compatibility.get(i).cloned().unwrap_or(false))
+				.collect::<Vec<_>>()
+				.into_boxed_slice(),
+		}
+	}
+
+	pub fn from_system(system: MatingSystem) -> Self {
+		match system {
+			MatingSystem::Hermaphrodite => Genders::new(1, &[true]),
+			MatingSystem::TwoSexes => Genders::new(2, &[false, true, true, false]),
+			MatingSystem::MatingTypes(n) => {
+				let n = cmp::min(cmp::max(n, 1), MAX_GENDERS);
+				let matrix = (0..n * n).map(|i| i / n != i % n).collect::<Vec<_>>();
+				Genders::new(n, &matrix)
+			}
+		}
+	}
+
+	pub fn count(&self) -> u8 {
+		self.count
+	}
+
+	// gender expressed by the raw gender gene, which is spread over 0..256
+	#[inline]
+	pub fn of(&self, gene: u8) -> u8 {
+		gene % self.count
+	}
+
+	pub fn compatible(&self, spore_gene: u8, mate_gene: u8) -> bool {
+		let (a, b) = (self.of(spore_gene) as usize, self.of(mate_gene) as usize);
+		self.compatibility[a * self.count as usize + b]
+	}
+}
+
+impl Default for Genders {
+	fn default() -> Self {
+		Genders::from_system(MatingSystem::MatingTypes(MAX_GENDERS))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hermaphrodites_mate_with_anyone() {
+		let genders = Genders::from_system(MatingSystem::Hermaphrodite);
+		assert_eq!(genders.count(), 1);
+		assert!(genders.compatible(0, 0));
+		assert!(genders.compatible(17, 200));
+	}
+
+	#[test]
+	fn two_sexes_need_one_of_each() {
+		let genders = Genders::from_system(MatingSystem::TwoSexes);
+		assert!(!genders.compatible(0, 0));
+		assert!(!genders.compatible(1, 1));
+		assert!(genders.compatible(0, 1));
+		assert!(genders.compatible(1, 0));
+	}
+
+	#[test]
+	fn mating_types_are_self_incompatible() {
+		let genders = Genders::from_system(MatingSystem::MatingTypes(3));
+		for a in 0..3 {
+			for b in 0..3 {
+				assert_eq!(genders.compatible(a, b), a != b);
+			}
+		}
+	}
+
+	#[test]
+	fn genes_split_evenly() {
+		for count in 1..MAX_GENDERS + 1 {
+			let genders = Genders::from_system(MatingSystem::MatingTypes(count));
+			let mut histogram = vec![0; count as usize];
+			for low in 0..4 {
+				for high in 0..64 {
+					histogram[genders.of(widen(low, high)) as usize] += 1;
+				}
+			}
+			let (min, max) = (*histogram.iter().min().unwrap(), *histogram.iter().max().unwrap());
+			assert!(max - min <= 1, "{:?}", histogram);
+		}
+	}
+
+	#[test]
+	fn widening_keeps_the_old_gene() {
+		for low in 0..4 {
+			assert_eq!(widen(low, 63) % 4, low);
+		}
+	}
+
+	#[test]
+	fn bad_input_is_clamped() {
+		let genders = Genders::new(0, &[]);
+		assert_eq!(genders.count(), 1);
+		assert!(!genders.compatible(0, 0));
+		let genders = Genders::new(9, &[true]);
+		assert_eq!(genders.count(), MAX_GENDERS);
+		assert!(genders.compatible(0, 0));
+		assert!(!genders.compatible(1, 2));
+	}
+}
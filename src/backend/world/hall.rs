@@ -0,0 +1,115 @@
+use std::io;
+use std::io::Write;
+use std::fs;
+use std::cmp::Ordering;
+use backend::world::gen::Dna;
+use backend::world::agent::Agent;
+use serialize::base64::{self, ToBase64};
+
+pub const FILE_NAME: &'static str = "hall_of_fame.csv";
+
+// How much each achievement is worth when ranking genomes
+#[derive(Clone, Debug)]
+pub struct Criteria {
+	// per second lived
+	pub lifespan: f32,
+	// per spore laid
+	pub offspring: f32,
+	// per unit of energy eaten
+	pub energy: f32,
+}
+
+impl Default for Criteria {
+	fn default() -> Self {
+		Criteria {
+			lifespan: 1.,
+			offspring: 5.,
+			energy: 0.01,
+		}
+	}
+}
+
+impl Criteria {
+	pub fn score(&self, agent: &Agent) -> f32 {
+		self.lifespan * agent.state.age() + self.offspring * agent.state.offspring() as f32 +
+		self.energy * agent.state.gathered()
+	}
+}
+
+// The best genomes seen so far, one entry per distinct DNA, best first
+pub struct HallOfFame {
+	criteria: Criteria,
+	capacity: usize,
+	entries: Vec<(f32, Dna)>,
+	dirty: bool,
+}
+
+impl HallOfFame {
+	pub fn new(capacity: usize, criteria: Criteria) -> Self {
+		HallOfFame {
+			criteria: criteria,
+			capacity: capacity,
+			entries: Vec::new(),
+			dirty: false,
+		}
+	}
+
+	// previously saved genomes have no score, they are the first to go
+	pub fn with_dna(mut self, dna: &[Dna]) -> Self {
+		for d in dna.iter().take(self.capacity) {
+			if !self.entries.iter().any(|&(_, ref e)| e == d) {
+				self.entries.push((0., d.clone()));
+			}
+		}
+		self
+	}
+
+	pub fn criteria(&self) -> &Criteria {
+		&self.criteria
+	}
+
+	pub fn set_criteria(&mut self, criteria: Criteria) {
+		self.criteria = criteria;
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn dna(&self) -> Box<[Dna]> {
+		self.entries.iter().map(|&(_, ref dna)| dna.clone()).collect::<Vec<_>>().into_boxed_slice()
+	}
+
+	pub fn record(&mut self, agent: &Agent) {
+		let score = self.criteria.score(agent);
+		if self.entries.len() >= self.capacity &&
+		   self.entries.last().map(|&(worst, _)| score <= worst).unwrap_or(false) {
+			return;
+		}
+		match self.entries.iter().position(|&(_, ref dna)| *dna == *agent.dna()) {
+			Some(i) if self.entries[i].0 >= score => return,
+			Some(i) => {
+				self.entries.remove(i);
+			}
+			None => {}
+		}
+		self.entries.push((score, agent.dna().clone()));
+		self.entries.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+		self.entries.truncate(self.capacity);
+		self.dirty = true;
+	}
+
+	// same format as the gene pool, so it can be loaded as a starting pool
+	pub fn save(&mut self) -> io::Result<Option<String>> {
+		if !self.dirty {
+			return Ok(None);
+		}
+		let file_name = format!("resources/{}", FILE_NAME);
+		let mut f = try!(fs::File::create(&file_name));
+		for &(_, ref dna) in &self.entries {
+			try!(f.write_fmt(format_args!("{}\n", dna.to_base64(base64::STANDARD))));
+		}
+		self.dirty = false;
+		Ok(Some(file_name))
+	}
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use backend::world::agent::AgentType;
+
+// Where the energy of the world came from and where it went during one tick.
+// Eating, laying and hatching only move energy around and are not booked.
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+	// new resources from the emitters and minions dropped in from outside
+	pub inflow: f32,
+	// burned by the minions to stay alive
+	pub metabolic: f32,
+	// eaten beyond storage, or carried by offspring that found no room
+	pub waste: f32,
+	// made anew when dead bodies turn into resources, their own energy is booked as decayed
+	pub created: f32,
+	// still held by agents when they were swept away
+	pub decayed: f32,
+	// held by every agent still in a swarm at the end of the tick
+	pub stored: HashMap<AgentType, f32>,
+}
+
+impl Ledger {
+	// adds up the flows, stock is only taken at the end of the tick
+	pub fn merge(&mut self, other: &Ledger) {
+		self.inflow += other.inflow;
+		self.metabolic += other.metabolic;
+		self.waste += other.waste;
+		self.created += other.created;
+		self.decayed += other.decayed;
+	}
+
+	pub fn total_stored(&self) -> f32 {
+		self.stored.values().fold(0., |a, &e| a + e)
+	}
+
+	// what the stored total should have changed by
+	pub fn balance(&self) -> f32 {
+		self.inflow + self.created - self.metabolic - self.waste - self.decayed
+	}
+}
+
+// The flows of the last window of ticks as amounts per second, and the latest stock
+pub struct Rate {
+	window: f32,
+	elapsed: f32,
+	ticks: VecDeque<(f32, Ledger)>,
+}
+
+impl Rate {
+	pub fn new(window: f32) -> Self {
+		Rate {
+			window: window,
+			elapsed: 0.,
+			ticks: VecDeque::new(),
+		}
+	}
+
+	pub fn push(&mut self, dt: f32, ledger: &Ledger) {
+		self.elapsed += dt;
+		self.ticks.push_back((dt, ledger.clone()));
+		while self.elapsed > self.window && self.ticks.len() > 1 {
+			if let Some((dt, _)) = self.ticks.pop_front() {
+				self.elapsed -= dt;
+			}
+		}
+	}
+
+	pub fn per_second(&self) -> Ledger {
+		let mut total = Ledger::default();
+		for &(_, ref ledger) in self.ticks.iter() {
+			total.merge(ledger);
+		}
+		if self.elapsed > 0. {
+			let k = 1. / self.elapsed;
+			total.inflow *= k;
+			total.metabolic *= k;
+			total.waste *= k;
+			total.created *= k;
+			total.decayed *= k;
+		}
+		if let Some(&(_, ref latest)) = self.ticks.back() {
+			total.stored = latest.stored.clone();
+		}
+		total
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use backend::world::agent::AgentType;
+
+	fn ledger(inflow: f32, metabolic: f32, stored: f32) -> Ledger {
+		let mut ledger = Ledger::default();
+		ledger.inflow = inflow;
+		ledger.metabolic = metabolic;
+		ledger.stored.insert(AgentType::Minion, stored);
+		ledger
+	}
+
+	#[test]
+	fn balance_adds_sources_and_takes_sinks() {
+		let mut l = Ledger::default();
+		l.inflow = 5.;
+		l.created = 1.;
+		l.metabolic = 2.;
+		l.waste = 0.5;
+		l.decayed = 0.25;
+		assert_eq!(l.balance(), 3.25);
+	}
+
+	#[test]
+	fn merge_adds_flows_only() {
+		let mut a = ledger(1., 2., 10.);
+		a.merge(&ledger(3., 4., 20.));
+		assert_eq!(a.inflow, 4.);
+		assert_eq!(a.metabolic, 6.);
+		assert_eq!(a.total_stored(), 10.);
+	}
+
+	#[test]
+	fn rate_is_per_second() {
+		let mut rate = Rate::new(1.);
+		for i in 0..4 {
+			rate.push(0.25, &ledger(1., 0.5, i as f32));
+		}
+		let l = rate.per_second();
+		assert_eq!(l.inflow, 4.);
+		assert_eq!(l.metabolic, 2.);
+		assert_eq!(l.total_stored(), 3.);
+	}
+
+	#[test]
+	fn rate_forgets_old_ticks() {
+		let mut rate = Rate::new(1.);
+		rate.push(0.5, &ledger(100., 0., 0.));
+		for _ in 0..4 {
+			rate.push(0.5, &ledger(1., 0., 0.));
+		}
+		assert_eq!(rate.per_second().inflow, 2.);
+	}
+}
@@ -3,19 +3,29 @@ pub mod agent;
 pub mod swarm;
 pub mod gen;
 pub mod phen;
+pub mod scent;
+pub mod gender;
+pub mod hall;
+pub mod ledger;
+pub mod flow;
+pub mod obstacle;
 
 use backend::obj;
 use backend::obj::*;
 use rand;
+use rand::Rng;
 use chrono::*;
 use std::f32::consts;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::io;
 use std::io::Write;
 use std::fs;
+use std::str::FromStr;
 
 use core::geometry::*;
+use cgmath::EuclideanVector;
 use core::resource::ResourceLoader;
 use backend::world::agent::Agent;
 use backend::world::agent::AgentType;
@@ -25,12 +35,23 @@ use serialize::base64::{self, ToBase64};
 
 pub struct World {
 	pub extent: Rect,
+	topology: Topology,
 	swarms: HashMap<AgentType, Swarm>,
 	emitters: Vec<Emitter>,
 	registered: HashSet<Id>,
 	extinctions: usize,
 	minion_gene_pool: gen::GenePool,
 	resource_gene_pool: gen::GenePool,
+	scent: scent::ScentField,
+	genders: gender::Genders,
+	recovery: Recovery,
+	graveyard: VecDeque<gen::Dna>,
+	hall_of_fame: hall::HallOfFame,
+	stopped: bool,
+	ledger: ledger::Ledger,
+	last_ledger: ledger::Ledger,
+	flow: flow::FlowField,
+	obstacles: Vec<obstacle::Obstacle>,
 }
 
 pub trait WorldState {
@@ -43,6 +64,69 @@ impl WorldState for World {
 	}
 }
 
+// how many dead minions are remembered for reseeding
+const GRAVEYARD_SIZE: usize = 100;
+const HALL_OF_FAME_SIZE: usize = 32;
+// rocks scattered around at the start, unless configured otherwise
+pub const N_OBSTACLES: usize = 6;
+// the whirls around the emitters, random ones get half the strength
+pub const VORTEX_STRENGTH: f32 = 10.;
+pub const VORTEX_RADIUS: f32 = 5.;
+
+// What to do when the last minion dies
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Recovery {
+	// respawn the whole gene pool
+	GenePool,
+	// respawn the last n minions that died
+	LastDead(usize),
+	// respawn the hall of fame, or the gene pool if there is none
+	HallOfFame,
+	// as many random genomes as there are in the gene pool
+	Random,
+	// stop the run
+	Stop,
+}
+
+// the variant name, with the count after it for LastDead, as in "LastDead 20"
+impl FromStr for Recovery {
+	type Err = ();
+	fn from_str(s: &str) -> Result<Self, ()> {
+		let mut words = s.split_whitespace();
+		let recovery = match words.next() {
+			Some("GenePool") => Recovery::GenePool,
+			Some("LastDead") => {
+				let n = try!(words.next().ok_or(()).and_then(|n| n.parse::<usize>().map_err(|_| ())));
+				Recovery::LastDead(n)
+			}
+			Some("HallOfFame") => Recovery::HallOfFame,
+			Some("Random") => Recovery::Random,
+			Some("Stop") => Recovery::Stop,
+			_ => return Err(()),
+		};
+		if words.next().is_some() { Err(()) } else { Ok(recovery) }
+	}
+}
+
+// What lies beyond the edges of the world
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+	// walls all around, whatever gets past them dies
+	Bounded,
+	// leaving from one edge means coming back from the opposite one
+	Toroidal,
+}
+
+impl Topology {
+	// the shortest way from one point to another
+	pub fn displacement(&self, extent: &Rect, from: &Position, to: &Position) -> Translation {
+		match *self {
+			Topology::Bounded => to - from,
+			Topology::Toroidal => extent.wrapped_delta(from, to),
+		}
+	}
+}
+
 #[derive(Clone)]
 pub enum Emission {
 	CW(Angle),
@@ -98,85 +182,227 @@ impl World {
 			                                   "GzB2lQdwM10vQEu5zwaPgDhfq2v8GzB2lQdwM10vQEu5zwaPgDhfq2v8"])
 		}
 
-		World {
-			extent: Rect::new(-80., -80., 80., 80.),
+		let extent = Rect::new(-80., -80., 80., 80.);
+		let emitters = vec![Emitter::new(-20., -20., 0.4, Emission::CW(consts::PI / 12.)),
+		                    Emitter::new(-20., 20., 0.4, Emission::Random),
+		                    Emitter::new(20., 20., 0.4, Emission::CCW(consts::PI / 12.)),
+		                    Emitter::new(20., -20., 0.4, Emission::Random)];
+		let mut world = World {
+			extent: extent,
+			topology: Topology::Bounded,
 			swarms: swarms,
-			emitters: vec![Emitter::new(-20., -20., 0.4, Emission::CW(consts::PI / 12.)),
-			               Emitter::new(-20., 20., 0.4, Emission::Random),
-			               Emitter::new(20., 20., 0.4, Emission::CCW(consts::PI / 12.)),
-			               Emitter::new(20., -20., 0.4, Emission::Random)],
+			emitters: emitters,
 			minion_gene_pool: res.load(minion_gene_pool)
 				.map(|data| gen::GenePool::parse_from_resource(&data))
 				.unwrap_or_else(default_gene_pool),
 			resource_gene_pool: gen::GenePool::parse_from_base64(&["GyA21QoQ", "M00sWS0M"]),
 			registered: HashSet::new(),
 			extinctions: 0usize,
+			scent: scent::ScentField::new(&extent, 2., 4., 5.),
+			genders: gender::Genders::default(),
+			recovery: Recovery::GenePool,
+			graveyard: VecDeque::new(),
+			hall_of_fame: hall::HallOfFame::new(HALL_OF_FAME_SIZE, hall::Criteria::default())
+				.with_dna(res.load(hall::FILE_NAME)
+					.map(|data| gen::GenePool::parse_from_resource(&data).dna().to_vec())
+					.unwrap_or(Vec::new())
+					.as_slice()),
+			stopped: false,
+			ledger: ledger::Ledger::default(),
+			last_ledger: ledger::Ledger::default(),
+			flow: flow::FlowField::default(),
+			obstacles: Vec::new(),
+		};
+		world.stir(VORTEX_STRENGTH, VORTEX_RADIUS);
+		world
+	}
+
+	// each emitter stirs the water the way it spins, random ones take turns
+	pub fn stir(&mut self, strength: f32, radius: f32) {
+		self.flow.vortices.clear();
+		for (i, e) in self.emitters.iter().enumerate() {
+			let spin = match e.emission {
+				Emission::CW(_) => -1.,
+				Emission::CCW(_) => 1.,
+				Emission::Random => if i % 2 == 0 { 0.5 } else { -0.5 },
+			};
+			self.flow.vortices.push(flow::Vortex {
+				center: e.transform.position,
+				strength: spin * strength,
+				radius: radius,
+			});
+		}
+	}
+
+	pub fn add_obstacle(&mut self, transform: &Transform, outline: obstacle::Outline) -> obj::Id {
+		let id = self.swarm_mut(&AgentType::Prop).next_id();
+		self.obstacles.push(obstacle::Obstacle::new(id, transform, outline));
+		id
+	}
+
+	// random rocks, clear of the emitters and of where the minions are seeded
+	pub fn scatter_obstacles(&mut self, n: usize) {
+		const CLEARANCE: f32 = 12.;
+		let mut rng = rand::thread_rng();
+		let spawn_radius = self.extent.top_right().x * 0.25 + self.minion_gene_pool.len() as f32 + CLEARANCE;
+		let (min, max) = (self.extent.min, self.extent.max);
+		let mut placed = 0;
+		for _ in 0..n * 10 {
+			if placed >= n {
+				break;
+			}
+			let p = Position::new(rng.gen_range(min.x + CLEARANCE, max.x - CLEARANCE),
+			                      rng.gen_range(min.y + CLEARANCE, max.y - CLEARANCE));
+			let near_emitter = self.emitters.iter().any(|e| (e.transform.position - p).length() < CLEARANCE);
+			if !near_emitter && p.length() > spawn_radius {
+				let angle = rng.gen_range(0., 2. * consts::PI);
+				self.add_obstacle(&Transform::new(p, angle), obstacle::Obstacle::random_outline());
+				placed += 1;
+			}
 		}
 	}
 
+	pub fn obstacles(&self) -> &[obstacle::Obstacle] {
+		self.obstacles.as_slice()
+	}
+
 	pub fn extinctions(&self) -> usize {
 		self.extinctions
 	}
 
-	pub fn new_resource(&mut self, transform: &Transform, motion: Option<&Motion>) -> obj::Id {
+	pub fn new_resource(&mut self, transform: &Transform, motion: Option<&Motion>) -> Option<obj::Id> {
 		let mut gen = &mut self.resource_gene_pool.next();
 		let id = self.swarm_mut(&AgentType::Resource)
 			.spawn::<phen::Resource>(&mut gen, transform, motion, 0.8);
-		self.register(id)
+		self.ledger.inflow += self.energy_of(id);
+		id.map(|id| self.register(id))
 	}
 
-	pub fn decay_to_resource(&mut self, transform: &Transform, dna: &gen::Dna) -> obj::Id {
+	pub fn decay_to_resource(&mut self, transform: &Transform, dna: &gen::Dna) -> Option<obj::Id> {
 		let id = self.swarm_mut(&AgentType::Resource)
 			.spawn::<phen::Resource>(&mut gen::Genome::new(dna), transform, None, 0.8);
-		self.register(id)
+		self.ledger.created += self.energy_of(id);
+		id.map(|id| self.register(id))
 	}
 
-	pub fn new_spore(&mut self, transform: &Transform, dna: &gen::Dna) -> obj::Id {
+	pub fn new_spore(&mut self, parent: obj::Id, transform: &Transform, motion: Option<&Motion>, energy: f32,
+	                 dna: &gen::Dna)
+	                 -> Option<obj::Id> {
 		let id = self.swarm_mut(&AgentType::Spore)
 			.spawn::<phen::Spore>(&mut gen::Genome::new(dna).mutate(&mut rand::thread_rng()),
 			                      transform,
-			                      None,
+			                      motion,
 			                      0.8);
-		self.register(id)
+		id.map(|id| {
+			if let Some(spore) = self.agent_mut(id) {
+				spore.state.provision(energy);
+				spore.state.add_parent(parent);
+			}
+			self.register(id)
+		})
 	}
 
-	pub fn hatch_spore(&mut self, transform: &Transform, dna: &gen::Dna) -> obj::Id {
+	pub fn hatch_spore(&mut self, transform: &Transform, energy: f32, dna: &gen::Dna) -> Option<obj::Id> {
 		let id = self.swarm_mut(&AgentType::Minion)
 			.spawn::<phen::Minion>(&mut gen::Genome::new(dna), transform, None, 0.3);
-		self.register(id)
+		id.map(|id| {
+			// what the hatchling cannot store is lost
+			let excess = self.agent_mut(id).map(|minion| minion.state.reset_energy(energy)).unwrap_or(0.);
+			self.ledger.waste += excess;
+			self.register(id)
+		})
 	}
 
-	pub fn randomize_minion(&mut self, pos: Position, motion: Option<&Motion>) -> obj::Id {
+	pub fn randomize_minion(&mut self, pos: Position, motion: Option<&Motion>) -> Option<obj::Id> {
 		self.minion_gene_pool.randomize();
 		self.new_minion(pos, motion)
 	}
 
-	pub fn init_minions(&mut self) {
-		self.extinctions += 1;
+	pub fn set_recovery(&mut self, recovery: Recovery) {
+		self.recovery = recovery;
+	}
+
+	pub fn topology(&self) -> Topology {
+		self.topology
+	}
+
+	pub fn set_topology(&mut self, topology: Topology) {
+		self.topology = topology;
+	}
+
+	pub fn displacement(&self, from: &Position, to: &Position) -> Translation {
+		self.topology.displacement(&self.extent, from, to)
+	}
+
+	pub fn is_stopped(&self) -> bool {
+		self.stopped
+	}
+
+	// repopulates an empty world according to the recovery policy
+	pub fn recover(&mut self) {
+		if self.stopped {
+			return;
+		}
 		let n = self.minion_gene_pool.len();
+		let recovery = self.recovery;
+		let genomes = match recovery {
+			Recovery::GenePool => Self::draw(&mut self.minion_gene_pool, n),
+			Recovery::LastDead(count) if !self.graveyard.is_empty() => {
+				let dead = self.graveyard.iter().take(count).cloned().collect::<Vec<_>>();
+				Self::draw(&mut gen::GenePool::new(&dead), dead.len())
+			}
+			Recovery::HallOfFame if !self.hall_of_fame.is_empty() => {
+				let dna = self.hall_of_fame.dna();
+				Self::draw(&mut gen::GenePool::new(&dna), dna.len())
+			}
+			Recovery::Random => Self::draw(&mut gen::GenePool::random(n), n),
+			Recovery::Stop if self.extinctions > 0 => {
+				self.stopped = true;
+				return;
+			}
+			// nothing to draw from yet, or the very first population
+			_ => Self::draw(&mut self.minion_gene_pool, n),
+		};
+		self.extinctions += 1;
+		self.save_hall_of_fame();
+		self.init_minions(genomes);
+	}
+
+	fn draw(gene_pool: &mut gen::GenePool, n: usize) -> Vec<gen::Genome> {
+		(0..n).map(|_| gene_pool.next()).collect()
+	}
+
+	fn init_minions(&mut self, genomes: Vec<gen::Genome>) {
 		let mut r = self.extent.top_right().x * 0.25;
 		let mut angle = 0.0f32;
 		let angle_delta = consts::PI * 2. / 16. as f32;
-		for _ in 0..n {
+		for mut gen in genomes {
 			let pos = Position::new(r * angle.cos(), r * angle.sin());
-			let mut gen = self.minion_gene_pool.next();
 			let id = self.swarm_mut(&AgentType::Minion)
 				.spawn::<phen::Minion>(&mut gen,
 				                       &Transform::new(pos, angle + consts::PI / 2.),
 				                       None,
 				                       0.3);
-			self.register(id);
+			self.ledger.inflow += self.energy_of(id);
+			if let Some(id) = id {
+				self.register(id);
+			}
 			angle += angle_delta;
 			r += 1.;
 		}
 	}
 
-	pub fn new_minion(&mut self, pos: Position, motion: Option<&Motion>) -> obj::Id {
+	pub fn new_minion(&mut self, pos: Position, motion: Option<&Motion>) -> Option<obj::Id> {
 		let angle = consts::PI / 2. + f32::atan2(pos.y, pos.x);
 		let mut gen = self.minion_gene_pool.next();
 		let id = self.swarm_mut(&AgentType::Minion)
 			.spawn::<phen::Minion>(&mut gen, &Transform::new(pos, angle), motion, 0.3);
-		self.register(id)
+		self.ledger.inflow += self.energy_of(id);
+		id.map(|id| self.register(id))
+	}
+
+	fn energy_of(&self, id: Option<obj::Id>) -> f32 {
+		id.and_then(|id| self.agent(id)).map(|agent| agent.state.energy()).unwrap_or(0.)
 	}
 
 	pub fn register(&mut self, id: obj::Id) -> obj::Id {
@@ -216,14 +442,55 @@ impl World {
 		self.swarms.get_mut(&agent_type).unwrap().agents_mut()
 	}
 
+	pub fn swarm(&self, agent_type: &AgentType) -> &Swarm {
+		self.swarms.get(&agent_type).unwrap()
+	}
+
 	pub fn swarm_mut(&mut self, agent_type: &AgentType) -> &mut Swarm {
 		self.swarms.get_mut(&agent_type).unwrap()
 	}
 
+	pub fn set_capacity(&mut self, agent_type: &AgentType, capacity: Capacity) {
+		self.swarm_mut(agent_type).set_capacity(capacity);
+	}
+
 	pub fn emitters(&self) -> &[Emitter] {
 		self.emitters.as_slice()
 	}
 
+	pub fn genders(&self) -> &gender::Genders {
+		&self.genders
+	}
+
+	pub fn set_genders(&mut self, genders: gender::Genders) {
+		self.genders = genders;
+	}
+
+	// number of minions for each gender
+	pub fn gender_distribution(&self) -> Box<[usize]> {
+		let mut distribution = vec![0usize; self.genders.count() as usize];
+		for (_, minion) in self.agents(AgentType::Minion).iter() {
+			distribution[self.genders.of(minion.gender()) as usize] += 1;
+		}
+		distribution.into_boxed_slice()
+	}
+
+	pub fn flow(&self) -> &flow::FlowField {
+		&self.flow
+	}
+
+	pub fn flow_mut(&mut self) -> &mut flow::FlowField {
+		&mut self.flow
+	}
+
+	pub fn scent(&self) -> &scent::ScentField {
+		&self.scent
+	}
+
+	pub fn scent_mut(&mut self) -> &mut scent::ScentField {
+		&mut self.scent
+	}
+
 	pub fn swarms(&self) -> &SwarmMap {
 		&self.swarms
 	}
@@ -233,9 +500,61 @@ impl World {
 		for (_, agents) in self.swarms.iter_mut() {
 			agents.free_resources(&mut v);
 		}
+		for agent in v.iter() {
+			self.ledger.decayed += agent.state.energy();
+		}
+		// most recent first
+		for agent in v.iter().filter(|a| a.id().type_of() == AgentType::Minion) {
+			self.graveyard.push_front(agent.dna().clone());
+			self.hall_of_fame.record(agent);
+		}
+		while self.graveyard.len() > GRAVEYARD_SIZE {
+			self.graveyard.pop_back();
+		}
 		v.into_boxed_slice()
 	}
 
+	pub fn ledger_mut(&mut self) -> &mut ledger::Ledger {
+		&mut self.ledger
+	}
+
+	// the last complete tick
+	pub fn ledger(&self) -> &ledger::Ledger {
+		&self.last_ledger
+	}
+
+	// takes stock of what the living hold and starts a new tick
+	pub fn close_ledger(&mut self) {
+		for (agent_type, swarm) in self.swarms.iter() {
+			// the dead still count until they are swept
+			let stored = swarm.agents()
+				.values()
+				.fold(0., |a, agent| a + agent.state.energy());
+			self.ledger.stored.insert(*agent_type, stored);
+		}
+		self.last_ledger = ::std::mem::replace(&mut self.ledger, ledger::Ledger::default());
+	}
+
+	pub fn hall_of_fame(&self) -> &hall::HallOfFame {
+		&self.hall_of_fame
+	}
+
+	pub fn hall_of_fame_mut(&mut self) -> &mut hall::HallOfFame {
+		&mut self.hall_of_fame
+	}
+
+	// the living get their chance too
+	pub fn save_hall_of_fame(&mut self) {
+		for (_, minion) in self.swarms.get(&AgentType::Minion).unwrap().agents().iter() {
+			self.hall_of_fame.record(minion);
+		}
+		match self.hall_of_fame.save() {
+			Err(_) => error!("Failed to save hall of fame"),
+			Ok(Some(name)) => info!("Saved {}", name),
+			Ok(None) => {}
+		}
+	}
+
 	pub fn dump(&self) -> io::Result<String> {
 		let now: DateTime<UTC> = UTC::now();
 		let file_name = now.format("resources/%Y%m%d_%H%M%S.csv").to_string();
@@ -0,0 +1,83 @@
+use std::f32::consts;
+use rand;
+use rand::Rng;
+use core::geometry::*;
+use backend::obj::*;
+
+#[derive(Clone, Debug)]
+pub enum Outline {
+	Circle { radius: f32 },
+	// convex, vertices around the local origin
+	Polygon { vertices: Box<[Position]> },
+	// open line, only its edges are solid
+	Chain { vertices: Box<[Position]> },
+}
+
+// Static scenery: rocks and walls nothing can move
+#[derive(Clone, Debug)]
+pub struct Obstacle {
+	id: Id,
+	transform: Transform,
+	pub outline: Outline,
+}
+
+impl Obstacle {
+	pub fn new(id: Id, transform: &Transform, outline: Outline) -> Self {
+		Obstacle {
+			id: id,
+			transform: transform.clone(),
+			outline: outline,
+		}
+	}
+
+	pub fn regular_polygon(n: usize, radius: f32) -> Outline {
+		Outline::Polygon {
+			vertices: (0..n)
+				.map(|i| {
+					let angle = i as f32 * 2. * consts::PI / n as f32;
+					Position::new(radius * angle.cos(), radius * angle.sin())
+				})
+				.collect::<Vec<_>>()
+				.into_boxed_slice(),
+		}
+	}
+
+	// a jagged open line across the origin, n points a step apart, wandering up to roughness either side
+	pub fn ridge(n: usize, step: f32, roughness: f32) -> Outline {
+		let mut rng = rand::thread_rng();
+		let x0 = -0.5 * step * (n - 1) as f32;
+		Outline::Chain {
+			vertices: (0..n)
+				.map(|i| Position::new(x0 + i as f32 * step, rng.gen_range(-roughness, roughness)))
+				.collect::<Vec<_>>()
+				.into_boxed_slice(),
+		}
+	}
+
+	// a random rock somewhere in the extent, either round, a polygon or a ridge
+	pub fn random_outline() -> Outline {
+		let mut rng = rand::thread_rng();
+		let radius = rng.gen_range(2., 6.);
+		match rng.gen_range(1, 8) {
+			// short enough to stay clear of the walls wherever it lands
+			1 => Self::ridge(rng.gen_range(3, 5), radius, radius * 0.5),
+			2 => Outline::Circle { radius: radius },
+			n => Self::regular_polygon(n, radius),
+		}
+	}
+}
+
+impl Identified for Obstacle {
+	fn id(&self) -> Id {
+		self.id
+	}
+}
+
+impl Transformable for Obstacle {
+	fn transform(&self) -> &Transform {
+		&self.transform
+	}
+	fn transform_to(&mut self, t: &Transform) {
+		self.transform = t.clone();
+	}
+}
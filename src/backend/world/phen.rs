@@ -9,6 +9,10 @@ use backend::world::agent;
 use backend::world::agent::N_WEIGHTS;
 use backend::world::agent::Agent;
 use backend::world::agent::Brain;
+use backend::world::agent::Vision;
+use backend::world::agent::Reproduction;
+use backend::world::agent::Preference;
+use backend::world::gender;
 use backend::world::agent::TypedBrain;
 use backend::world::gen::*;
 use cgmath;
@@ -46,7 +50,7 @@ impl Phenotype for Minion {
 		                                    Livery { albedo: albedo.to_rgba(), ..Default::default() },
 		                                    gen.dna(),
 		                                    segment::State::with_charge(0., charge, charge));
-		builder.gender(gender);
+		builder.gender(gender).tint(tint);
 
 		// personality parameters
 		let mut weights_in = [[0.; N_WEIGHTS]; N_WEIGHTS];
@@ -103,8 +107,64 @@ impl Phenotype for Minion {
 		let leg_shape = gen.star();
 		builder.addr(belly, belly_mid - 1, &leg_shape, LEG | ACTUATOR | THRUSTER)
 			.addl(belly, 1 - belly_mid, &leg_shape, LEG | ACTUATOR | THRUSTER)
-			.add(belly, belly_mid, &tail_shape, TAIL | ACTUATOR | BRAKE)
-			.build()
+			.add(belly, belly_mid, &tail_shape, TAIL | ACTUATOR | BRAKE);
+		// field of view, temper, lifespan and breeding, decoded last so they do not shift the body plan genes
+		builder.vision(&Vision {
+				fov: gen.next_float(consts::PI * 0.25, consts::PI * 1.5),
+				rays: gen.next_integer::<u8>(1, 5) as usize,
+				range: gen.next_float(5., 15.),
+			})
+			.aggression(&gen.next_float(0., 1.))
+			.max_age(gen.next_float(60., 300.))
+			.reproduction(&Reproduction {
+				interval: gen.next_float(2., 10.),
+				investment: gen.next_float(0.3, 0.9),
+				clutch: gen.next_integer::<u8>(1, 4) as usize,
+				dispersal: gen.next_float(0., 5.),
+				maturity: gen.next_float(0., 30.),
+				mating_effort: gen.next_float(0., 0.2),
+			});
+		// which joints get a motor: arms, belly or both
+		let motors = gen.next_integer::<u8>(0, 3);
+		// how each segment hangs on its parent. Every segment reads the same genes
+		// whatever joint it ends up with, so a flip does not shift the ones after it
+		for index in 1..builder.len() {
+			let flip = gen.next_integer::<u8>(0, 3) == 0;
+			let lower = -gen.next_float(0., consts::PI / 2.);
+			let upper = gen.next_float(0., consts::PI / 2.);
+			let frequency = gen.next_float(1., 10.);
+			let damping_ratio = gen.next_float(0.1, 1.);
+			let revolute = builder.segment(index as SegmentIndex).flags.contains(JOINT) != flip;
+			builder.joint(index as SegmentIndex,
+			              if revolute {
+				              segment::Joint::Revolute {
+					              lower: lower,
+					              upper: upper,
+				              }
+			              } else {
+				              segment::Joint::Weld {
+					              frequency: frequency,
+					              damping_ratio: damping_ratio,
+				              }
+			              });
+		}
+		// only now is it known which joints are hinges
+		if motors & 0x1 != 0 {
+			builder.motorise(ARM);
+		}
+		if motors & 0x2 != 0 {
+			builder.motorise(STORAGE);
+		}
+		builder.gender(gender::widen(gender, gen.next_integer::<u8>(0, 63)));
+		builder.angular_damping(gen.next_float(0.1, 2.));
+		let mut weights_scent = [[0.; N_WEIGHTS]; N_WEIGHTS];
+		for i in 0..N_WEIGHTS {
+			for j in 0..N_WEIGHTS {
+				weights_scent[i][j] = gen.next_float(-4., 4.);
+			}
+		}
+		builder.weights_scent(&weights_scent);
+		builder.build()
 	}
 }
 
@@ -119,7 +179,14 @@ impl Phenotype for Spore {
 		                                    Livery { albedo: albedo.to_rgba(), ..Default::default() },
 		                                    gen.dna(),
 		                                    segment::State::with_charge(0., charge, charge));
-		builder.gender(gender).start(transform, motion, &gen.ball()).build()
+		builder.gender(gender).tint(tint).start(transform, motion, &gen.ball());
+		builder.preference(&Preference {
+			tint: gen.next_float(0., 1.),
+			tolerance: gen.next_float(0.05, 0.5),
+			min_size: gen.next_float(0., 10.),
+		});
+		builder.gender(gender::widen(gender, gen.next_integer::<u8>(0, 63)));
+		builder.build()
 	}
 }
 
@@ -128,7 +195,12 @@ pub struct AgentBuilder {
 	material: Material,
 	livery: Livery,
 	gender: u8,
+	tint: f32,
 	brain: Brain,
+	vision: Vision,
+	reproduction: Reproduction,
+	preference: Preference,
+	max_age: f32,
 	dna: Dna,
 	state: segment::State,
 	segments: Vec<Segment>,
@@ -142,7 +214,12 @@ impl AgentBuilder {
 			livery: livery,
 			state: state,
 			gender: 0u8,
+			tint: 0.,
 			brain: Brain::default(),
+			vision: Vision::default(),
+			reproduction: Reproduction::default(),
+			preference: Preference::default(),
+			max_age: ::std::f32::INFINITY,
 			dna: dna.clone(),
 			segments: Vec::new(),
 		}
@@ -166,6 +243,12 @@ impl AgentBuilder {
 		self
 	}
 
+	#[inline]
+	pub fn tint(&mut self, tint: f32) -> &mut Self {
+		self.tint = tint;
+		self
+	}
+
 	#[inline]
 	pub fn add(&mut self, parent_index: SegmentIndex, attachment_index_offset: isize, shape: &Shape,
 	           flags: segment::Flags)
@@ -215,12 +298,47 @@ impl AgentBuilder {
 		                               &Transform::new(parent_pos + (p0.normalize_to(r0 + r1)),
 		                                               consts::PI / 2. + angle),
 		                               None,
-		                               parent.new_attachment(attachment_index as AttachmentIndex),
+		                               parent.new_attachment(attachment_index as AttachmentIndex,
+		                                                     segment::Joint::default_for(flags)),
 		                               flags);
 		self.segments.push(segment);
 		self
 	}
 
+	// turns the revolute joints of the matching segments into motors, welds are left alone
+	pub fn motorise(&mut self, flags: segment::Flags) -> &mut Self {
+		for segment in self.segments.iter_mut().filter(|s| s.flags.intersects(flags)) {
+			if let Some(segment::Attachment { joint: segment::Joint::Revolute { .. }, .. }) = segment.attached_to {
+				segment.flags = segment.flags | segment::MOTOR;
+			}
+		}
+		self
+	}
+
+	// for the whole body, segments added later included
+	pub fn angular_damping(&mut self, angular_damping: f32) -> &mut Self {
+		self.material.angular_damping = angular_damping;
+		for segment in self.segments.iter_mut() {
+			segment.material.angular_damping = angular_damping;
+		}
+		self
+	}
+
+	pub fn len(&self) -> usize {
+		self.segments.len()
+	}
+
+	pub fn segment(&self, index: SegmentIndex) -> &segment::Segment {
+		&self.segments[index as usize]
+	}
+
+	pub fn joint(&mut self, index: SegmentIndex, joint: segment::Joint) -> &mut Self {
+		if let Some(ref mut attachment) = self.segments[index as usize].attached_to {
+			attachment.joint = joint;
+		}
+		self
+	}
+
 	pub fn index(&self) -> SegmentIndex {
 		match self.segments.len() {
 			0 => 0,
@@ -248,6 +366,11 @@ impl AgentBuilder {
 		self
 	}
 
+	pub fn aggression(&mut self, value: &<Brain as TypedBrain>::Parameter) -> &mut Self {
+		self.brain.aggression = value.clone();
+		self
+	}
+
 	pub fn rest(&mut self, value: &<Brain as TypedBrain>::Parameter) -> &mut Self {
 		self.brain.rest = value.clone();
 		self
@@ -258,6 +381,26 @@ impl AgentBuilder {
 		self
 	}
 
+	pub fn vision(&mut self, vision: &Vision) -> &mut Self {
+		self.vision = vision.clone();
+		self
+	}
+
+	pub fn reproduction(&mut self, reproduction: &Reproduction) -> &mut Self {
+		self.reproduction = reproduction.clone();
+		self
+	}
+
+	pub fn preference(&mut self, preference: &Preference) -> &mut Self {
+		self.preference = preference.clone();
+		self
+	}
+
+	pub fn max_age(&mut self, max_age: f32) -> &mut Self {
+		self.max_age = max_age;
+		self
+	}
+
 	pub fn weights_in(&mut self, weights_in: &<Brain as TypedBrain>::WeightMatrix) -> &mut Self {
 		self.brain.weights_in = weights_in.clone();
 		self
@@ -273,6 +416,11 @@ impl AgentBuilder {
 		self
 	}
 
+	pub fn weights_scent(&mut self, weights_scent: &<Brain as TypedBrain>::WeightMatrix) -> &mut Self {
+		self.brain.weights_scent = weights_scent.clone();
+		self
+	}
+
 	fn new_segment(&mut self, shape: &Shape, winding: Winding, transform: &Transform, motion: Option<&Motion>,
 	               attachment: Option<segment::Attachment>, flags: segment::Flags)
 	               -> segment::Segment {
@@ -293,7 +441,12 @@ impl AgentBuilder {
 		trace!("Agent {:?} has brain {:?}", self.id, self.brain);
 		Agent::new(self.id,
 		           self.gender,
+		           self.tint,
 		           &self.brain,
+		           &self.vision,
+		           &self.reproduction,
+		           &self.preference,
+		           self.max_age,
 		           &self.dna,
 		           self.segments.clone().into_boxed_slice())
 	}
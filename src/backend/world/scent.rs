@@ -0,0 +1,87 @@
+use std::cmp;
+use core::geometry::*;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Channel {
+	Food = 0,
+	Minion = 1,
+}
+
+const N_CHANNELS: usize = 2;
+
+// Scalar concentration fields over a regular grid covering the world extent.
+// Agents deposit into it, and every tick it spreads out and fades away.
+pub struct ScentField {
+	extent: Rect,
+	cell_size: f32,
+	cols: usize,
+	rows: usize,
+	diffusion: f32,
+	half_life: f32,
+	channels: [Vec<f32>; N_CHANNELS],
+	buffer: Vec<f32>,
+}
+
+impl ScentField {
+	pub fn new(extent: &Rect, cell_size: f32, diffusion: f32, half_life: f32) -> Self {
+		let cols = cmp::max(((extent.max.x - extent.min.x) / cell_size).ceil() as usize, 1);
+		let rows = cmp::max(((extent.max.y - extent.min.y) / cell_size).ceil() as usize, 1);
+		ScentField {
+			extent: *extent,
+			cell_size: cell_size,
+			cols: cols,
+			rows: rows,
+			diffusion: diffusion,
+			half_life: half_life,
+			channels: [vec![0.; cols * rows], vec![0.; cols * rows]],
+			buffer: vec![0.; cols * rows],
+		}
+	}
+
+	#[inline]
+	fn cell(&self, p: &Position) -> (usize, usize) {
+		let c = ((p.x - self.extent.min.x) / self.cell_size).floor();
+		let r = ((p.y - self.extent.min.y) / self.cell_size).floor();
+		(if c < 0. { 0 } else { cmp::min(c as usize, self.cols - 1) },
+		 if r < 0. { 0 } else { cmp::min(r as usize, self.rows - 1) })
+	}
+
+	pub fn deposit(&mut self, channel: Channel, p: &Position, amount: f32) {
+		let (c, r) = self.cell(p);
+		let cols = self.cols;
+		self.channels[channel as usize][r * cols + c] += amount;
+	}
+
+	// central differences in world units, pointing uphill
+	pub fn gradient(&self, channel: Channel, p: &Position) -> Position {
+		let (c, r) = self.cell(p);
+		let values = &self.channels[channel as usize];
+		let at = |c: usize, r: usize| values[r * self.cols + c];
+		let (c0, c1) = (if c > 0 { c - 1 } else { c }, cmp::min(c + 1, self.cols - 1));
+		let (r0, r1) = (if r > 0 { r - 1 } else { r }, cmp::min(r + 1, self.rows - 1));
+		let dx = if c1 > c0 { (at(c1, r) - at(c0, r)) / ((c1 - c0) as f32 * self.cell_size) } else { 0. };
+		let dy = if r1 > r0 { (at(c, r1) - at(c, r0)) / ((r1 - r0) as f32 * self.cell_size) } else { 0. };
+		Position::new(dx, dy)
+	}
+
+	// explicit 5-point laplacian with zero flux at the borders, then exponential decay
+	pub fn update(&mut self, dt: f32) {
+		let (cols, rows) = (self.cols, self.rows);
+		// keep the explicit scheme stable
+		let k = (self.diffusion * dt / (self.cell_size * self.cell_size)).min(0.25);
+		let decay = (-dt * 2f32.ln() / self.half_life).exp();
+		for channel in self.channels.iter_mut() {
+			for r in 0..rows {
+				for c in 0..cols {
+					let v = channel[r * cols + c];
+					let l = if c > 0 { channel[r * cols + c - 1] } else { v };
+					let rt = if c + 1 < cols { channel[r * cols + c + 1] } else { v };
+					let d = if r > 0 { channel[(r - 1) * cols + c] } else { v };
+					let u = if r + 1 < rows { channel[(r + 1) * cols + c] } else { v };
+					self.buffer[r * cols + c] = (v + k * (l + rt + d + u - 4. * v)) * decay;
+				}
+			}
+			channel.copy_from_slice(&self.buffer);
+		}
+	}
+}
@@ -1,3 +1,4 @@
+use std::f32::consts;
 use backend::obj;
 use backend::obj::*;
 use backend::world::agent;
@@ -13,6 +14,15 @@ pub enum Intent {
 	RunAway(Position),
 }
 
+// Something touching a segment during the last step, one per other segment
+// however many of their fixtures touched
+#[derive(Copy, Clone, Debug)]
+pub struct Contact {
+	pub other: agent::Key,
+	// the strongest push between the two
+	pub impulse: f32,
+}
+
 #[derive(Clone)]
 pub struct State {
 	age_seconds: f32,
@@ -21,8 +31,15 @@ pub struct State {
 	target_charge: f32,
 	recharge: f32,
 	smooth: math::Exponential<f32, f32>,
+	health: f32,
+	severed: bool,
 	pub intent: Intent,
-	pub last_touched: Option<agent::Key>,
+	contacts: Vec<Contact>,
+	// angular speed and torque asked of the motor driving the joint to the parent
+	pub motor_speed: f32,
+	pub motor_torque: f32,
+	// what the motor actually did during the last step
+	pub motor_work: f32,
 }
 
 impl Default for State {
@@ -34,8 +51,13 @@ impl Default for State {
 			target_charge: 0.,
 			recharge: 1.,
 			smooth: math::Exponential::new(1., 1., 2.),
+			health: 1.,
+			severed: false,
 			intent: Intent::Idle,
-			last_touched: None,
+			contacts: Vec::new(),
+			motor_speed: 0.,
+			motor_torque: 0.,
+			motor_work: 0.,
 		}
 	}
 }
@@ -54,6 +76,39 @@ impl State {
 		self.target_charge = target_charge;
 	}
 
+	pub fn contacts(&self) -> &[Contact] {
+		self.contacts.as_slice()
+	}
+
+	pub fn set_contacts(&mut self, contacts: &[Contact]) {
+		self.contacts = contacts.to_vec();
+	}
+
+	pub fn max_impulse(&self) -> f32 {
+		self.contacts.iter().fold(0., |a, c| a.max(c.impulse))
+	}
+
+	pub fn health(&self) -> f32 {
+		self.health
+	}
+
+	pub fn damage(&mut self, q: f32) {
+		self.health = (self.health - q).max(0.);
+	}
+
+	// detached from the body, its physics is gone and it no longer takes part in anything
+	// physics forgets a severed segment, so nothing would ever clear what it last touched
+	pub fn sever(&mut self) {
+		self.health = 0.;
+		self.severed = true;
+		self.contacts.clear();
+	}
+
+	#[inline]
+	pub fn is_severed(&self) -> bool {
+		self.severed
+	}
+
 	pub fn update(&mut self, dt: f32) {
 		self.age_seconds += dt;
 		self.age_frames += 1;
@@ -75,10 +130,36 @@ impl State {
 	}
 }
 
+// How a segment is held to its parent
+#[derive(Copy, Clone, Debug)]
+pub enum Joint {
+	// a hinge free to turn between the limits
+	Revolute { lower: Angle, upper: Angle },
+	// a spring, stiffer at higher frequencies
+	Weld { frequency: f32, damping_ratio: f32 },
+}
+
+impl Joint {
+	pub fn default_for(flags: Flags) -> Self {
+		if flags.contains(JOINT) {
+			Joint::Revolute {
+				lower: -consts::PI / 6.,
+				upper: consts::PI / 6.,
+			}
+		} else {
+			Joint::Weld {
+				frequency: 5.0,
+				damping_ratio: 0.9,
+			}
+		}
+	}
+}
+
 #[derive(Copy, Clone)]
 pub struct Attachment {
 	pub index: SegmentIndex,
 	pub attachment_point: AttachmentIndex,
+	pub joint: Joint,
 }
 
 bitflags! {
@@ -100,6 +181,7 @@ bitflags! {
 		const THRUSTER     = 0x10000u32,
 		const RUDDER       = 0x20000u32,
 		const BRAKE        = 0x40000u32,
+		const MOTOR        = 0x80000u32,
 	}
 }
 
@@ -117,13 +199,18 @@ pub struct Segment {
 }
 
 impl Segment {
-	pub fn new_attachment(&self, attachment_point: AttachmentIndex) -> Option<Attachment> {
+	pub fn new_attachment(&self, attachment_point: AttachmentIndex, joint: Joint) -> Option<Attachment> {
 		let max = self.mesh.vertices.len() as AttachmentIndex;
 		Some(Attachment {
 			index: self.index,
 			attachment_point: if attachment_point < max { attachment_point } else { max - 1 },
+			joint: joint,
 		})
 	}
+
+	pub fn mass(&self) -> f32 {
+		self.mesh.area() * self.material.density
+	}
 }
 
 impl obj::Drawable for Segment {
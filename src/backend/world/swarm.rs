@@ -1,4 +1,7 @@
 use backend::obj::*;
+use std::cmp;
+use std::usize;
+use std::str::FromStr;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use core::geometry::*;
@@ -9,10 +12,71 @@ use backend::world::agent::AgentType;
 use backend::world::agent::TypedAgent;
 use backend::world::gen::*;
 
+// What happens when a swarm grows past its soft cap
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapPolicy {
+	// no more births
+	Refuse,
+	// births go on, but upkeep rises with crowding
+	Tax,
+	// the oldest agents die to make room
+	CullOldest,
+}
+
+impl FromStr for CapPolicy {
+	type Err = ();
+	fn from_str(s: &str) -> Result<Self, ()> {
+		match s {
+			"Refuse" => Ok(CapPolicy::Refuse),
+			"Tax" => Ok(CapPolicy::Tax),
+			"CullOldest" => Ok(CapPolicy::CullOldest),
+			_ => Err(()),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Capacity {
+	pub soft: usize,
+	pub hard: usize,
+	pub policy: CapPolicy,
+}
+
+impl Capacity {
+	pub fn new(soft: usize, hard: usize, policy: CapPolicy) -> Self {
+		Capacity {
+			soft: soft,
+			hard: cmp::max(soft, hard),
+			policy: policy,
+		}
+	}
+
+	pub fn unbounded() -> Self {
+		Capacity::new(usize::MAX, usize::MAX, CapPolicy::Refuse)
+	}
+
+}
+
+// "soft hard policy", as in "100 200 Tax"
+impl FromStr for Capacity {
+	type Err = ();
+	fn from_str(s: &str) -> Result<Self, ()> {
+		let words = s.split_whitespace().collect::<Vec<_>>();
+		if words.len() != 3 {
+			return Err(());
+		}
+		let soft = try!(words[0].parse::<usize>().map_err(|_| ()));
+		let hard = try!(words[1].parse::<usize>().map_err(|_| ()));
+		let policy = try!(words[2].parse::<CapPolicy>());
+		Ok(Capacity::new(soft, hard, policy))
+	}
+}
+
 pub struct Swarm {
 	seq: Id,
 	agent_type: AgentType,
 	agents: agent::AgentMap,
+	capacity: Capacity,
 }
 
 impl Swarm {
@@ -21,9 +85,64 @@ impl Swarm {
 			seq: 0,
 			agent_type: agent_type,
 			agents: HashMap::new(),
+			capacity: Capacity::unbounded(),
 		}
 	}
 
+	pub fn capacity(&self) -> &Capacity {
+		&self.capacity
+	}
+
+	pub fn set_capacity(&mut self, capacity: Capacity) {
+		self.capacity = capacity;
+	}
+
+	// only living agents count, the dead are swept at the end of the tick
+	pub fn population(&self) -> usize {
+		self.agents.values().filter(|agent| agent.state.is_alive()).count()
+	}
+
+	pub fn has_room(&self) -> bool {
+		let n = self.population();
+		n < self.capacity.hard && (n < self.capacity.soft || self.capacity.policy != CapPolicy::Refuse)
+	}
+
+	// 0 up to the soft cap, growing linearly to 1 at the hard cap
+	pub fn crowding(&self) -> f32 {
+		let n = self.population();
+		if n <= self.capacity.soft {
+			0.
+		} else if self.capacity.hard <= self.capacity.soft {
+			1.
+		} else {
+			f32::min(1., (n - self.capacity.soft) as f32 / (self.capacity.hard - self.capacity.soft) as f32)
+		}
+	}
+
+	// kills the oldest living agents above the soft cap, returns how many died.
+	// Ids grow with every spawn, so the lowest ids are the oldest of any type
+	pub fn cull(&mut self) -> usize {
+		if self.capacity.policy != CapPolicy::CullOldest {
+			return 0;
+		}
+		let mut living = self.agents
+			.values()
+			.filter(|agent| agent.state.is_alive())
+			.map(|agent| agent.id())
+			.collect::<Vec<_>>();
+		if living.len() <= self.capacity.soft {
+			return 0;
+		}
+		living.sort();
+		let excess = living.len() - self.capacity.soft;
+		for &id in living.iter().take(excess) {
+			if let Some(agent) = self.agents.get_mut(&id) {
+				agent.state.die();
+			}
+		}
+		excess
+	}
+
 	#[allow(dead_code)]
 	pub fn type_of(&self) -> AgentType {
 		self.agent_type
@@ -58,15 +177,19 @@ impl Swarm {
 		}
 	}
 
-	pub fn spawn<T>(&mut self, genome: &mut Genome, transform: &Transform, motion: Option<&Motion>, charge: f32) -> Id
+	pub fn spawn<T>(&mut self, genome: &mut Genome, transform: &Transform, motion: Option<&Motion>, charge: f32)
+	                -> Option<Id>
 		where T: phen::Phenotype {
+		if !self.has_room() {
+			return None;
+		}
 		let id = self.next_id();
 		match id.type_of() {
 			AgentType::Minion | AgentType::Spore => info!("spawn: {} as {}", genome, id.type_of()),
 			_ => {}
 		}
 		let entity = T::develop(genome, id, transform, motion, charge);
-		self.insert(entity)
+		Some(self.insert(entity))
 	}
 
 	fn insert(&mut self, agent: Agent) -> Id {
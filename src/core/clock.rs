@@ -1,8 +1,26 @@
 use std::time;
 use std::fmt;
+use std::cell::Cell;
 
 pub type SystemStopwatch = time::SystemTime;
 
+// Seconds of simulated time, which only go by when the backend steps
+thread_local!(static SIMULATED_SECONDS: Cell<f64> = Cell::new(0.));
+
+pub fn advance_simulation(dt: f32) {
+	SIMULATED_SECONDS.with(|t| t.set(t.get() + dt as f64));
+}
+
+pub fn simulated_seconds() -> f64 {
+	SIMULATED_SECONDS.with(|t| t.get())
+}
+
+// A stopwatch on the simulated clock, for whatever must keep pace with the world whatever the frame rate
+#[derive(Clone, Copy)]
+pub struct SimulationStopwatch {
+	start: f64,
+}
+
 pub trait Stopwatch: Sized {
 	fn new() -> Self;
 
@@ -68,6 +86,12 @@ impl<T: Stopwatch> Hourglass<T> {
 		f32::max(0., dt)
 	}
 
+	// how long ago it ran out, zero if it hasn't yet
+	pub fn overtime(&self) -> f32 {
+		let dt = self.stopwatch.seconds() - self.timeout;
+		f32::max(0., dt)
+	}
+
 	pub fn is_expired(&self) -> bool {
 		let dt = self.left();
 		let e = dt <= 0.;
@@ -87,3 +111,32 @@ impl Stopwatch for SystemStopwatch {
 		}
 	}
 }
+
+impl Stopwatch for SimulationStopwatch {
+	fn new() -> Self {
+		SimulationStopwatch { start: simulated_seconds() }
+	}
+
+	fn seconds(&self) -> f32 {
+		(simulated_seconds() - self.start) as f32
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn simulated_time_only_goes_by_when_advanced() {
+		let mut hourglass = Hourglass::<SimulationStopwatch>::new(1.);
+		assert_eq!(hourglass.seconds(), 0.);
+		advance_simulation(0.75);
+		assert_eq!(hourglass.left(), 0.25);
+		assert!(!hourglass.is_expired());
+		advance_simulation(0.5);
+		assert!(hourglass.is_expired());
+		assert_eq!(hourglass.overtime(), 0.25);
+		hourglass.renew();
+		assert_eq!(hourglass.left(), 1.);
+	}
+}
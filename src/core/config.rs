@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// Plain "key = value" settings, one per line, with '#' starting a comment.
+// Anything that does not parse is left out, so the caller falls back to its defaults
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+	values: HashMap<String, String>,
+}
+
+impl Config {
+	pub fn parse(text: &str) -> Self {
+		let mut values = HashMap::new();
+		for line in text.lines() {
+			let line = line.split('#').next().unwrap_or("").trim();
+			if let Some(i) = line.find('=') {
+				let (key, value) = (line[..i].trim(), line[i + 1..].trim());
+				if !key.is_empty() {
+					values.insert(key.to_owned(), value.to_owned());
+				}
+			} else if !line.is_empty() {
+				warn!("config: ignoring '{}'", line);
+			}
+		}
+		Config { values: values }
+	}
+
+	pub fn parse_from_resource(data: &[u8]) -> Self {
+		Self::parse(&String::from_utf8_lossy(data))
+	}
+
+	pub fn get_str(&self, key: &str) -> Option<&str> {
+		self.values.get(key).map(|value| value.as_str())
+	}
+
+	pub fn get<T>(&self, key: &str) -> Option<T>
+		where T: FromStr {
+		self.get_str(key).and_then(|value| match value.parse::<T>() {
+			Ok(v) => Some(v),
+			Err(_) => {
+				warn!("config: bad value for {}: '{}'", key, value);
+				None
+			}
+		})
+	}
+
+	// whitespace separated values, all of which must parse
+	pub fn get_list<T>(&self, key: &str) -> Option<Vec<T>>
+		where T: FromStr {
+		self.get_str(key).and_then(|value| {
+			let list = value.split_whitespace().map(|v| v.parse::<T>().ok()).collect::<Option<Vec<_>>>();
+			if list.is_none() {
+				warn!("config: bad list for {}: '{}'", key, value);
+			}
+			list
+		})
+	}
+
+	pub fn get_or<T>(&self, key: &str, default: T) -> T
+		where T: FromStr {
+		self.get(key).unwrap_or(default)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_keys_and_values() {
+		let config = Config::parse("a = 1\n  b=two words  \n");
+		assert_eq!(config.get::<i32>("a"), Some(1));
+		assert_eq!(config.get_str("b"), Some("two words"));
+		assert_eq!(config.get_str("c"), None);
+	}
+
+	#[test]
+	fn skips_comments_and_junk() {
+		let config = Config::parse("# a = 1\nb = 2 # two\njunk\n= 3\n");
+		assert_eq!(config.get_str("a"), None);
+		assert_eq!(config.get::<f32>("b"), Some(2.));
+		assert_eq!(config.get_str(""), None);
+	}
+
+	#[test]
+	fn bad_values_fall_back() {
+		let config = Config::parse("a = x\nl = 1 2 x\nm = 1 2 3");
+		assert_eq!(config.get::<i32>("a"), None);
+		assert_eq!(config.get_or("a", 7), 7);
+		assert_eq!(config.get_list::<u32>("l"), None);
+		assert_eq!(config.get_list::<u32>("m"), Some(vec![1, 2, 3]));
+	}
+}
@@ -58,6 +58,36 @@ impl Rect {
 	pub fn top_left(&self) -> Position {
 		Position::new(self.min.x, self.max.y)
 	}
+
+	pub fn width(&self) -> f32 {
+		self.max.x - self.min.x
+	}
+
+	pub fn height(&self) -> f32 {
+		self.max.y - self.min.y
+	}
+
+	pub fn contains(&self, p: &Position) -> bool {
+		p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+	}
+
+	// the same point on a torus, brought back inside
+	pub fn wrap(&self, p: &Position) -> Position {
+		fn wrap1(x: f32, min: f32, size: f32) -> f32 {
+			min + (x - min) - ((x - min) / size).floor() * size
+		}
+		Position::new(wrap1(p.x, self.min.x, self.width()),
+		              wrap1(p.y, self.min.y, self.height()))
+	}
+
+	// the shortest way from one point to another on a torus
+	pub fn wrapped_delta(&self, from: &Position, to: &Position) -> Translation {
+		fn shortest(d: f32, size: f32) -> f32 {
+			d - (d / size).round() * size
+		}
+		Translation::new(shortest(to.x - from.x, self.width()),
+		                 shortest(to.y - from.y, self.height()))
+	}
 }
 
 impl Initial for Position {
@@ -140,3 +170,63 @@ impl PolygonType {
 		self.count[VertexType::Flat as usize] > 0
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(a: Position, b: Position) {
+		assert!((a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4,
+		        "({}, {}) != ({}, {})",
+		        a.x,
+		        a.y,
+		        b.x,
+		        b.y);
+	}
+
+	#[test]
+	fn wrap_leaves_inside_points_alone() {
+		let rect = Rect::new(-10., -5., 10., 5.);
+		let p = Position::new(3., -4.);
+		assert_close(rect.wrap(&p), p);
+		assert_close(rect.wrap(&rect.min), rect.min);
+	}
+
+	#[test]
+	fn wrap_brings_points_back_from_the_other_side() {
+		let rect = Rect::new(-10., -5., 10., 5.);
+		assert_close(rect.wrap(&Position::new(12., 0.)), Position::new(-8., 0.));
+		assert_close(rect.wrap(&Position::new(-12., 0.)), Position::new(8., 0.));
+		assert_close(rect.wrap(&Position::new(0., 6.)), Position::new(0., -4.));
+		// more than once around
+		assert_close(rect.wrap(&Position::new(53., -27.)), Position::new(-7., 3.));
+		assert!(rect.contains(&rect.wrap(&Position::new(-1000.5, 777.25))));
+	}
+
+	#[test]
+	fn wrapped_delta_takes_the_short_way() {
+		let rect = Rect::new(-10., -5., 10., 5.);
+		let near_edge = Position::new(9., 4.);
+		let across = Position::new(-9., -4.);
+		assert_close(rect.wrapped_delta(&near_edge, &across), Translation::new(2., 2.));
+		assert_close(rect.wrapped_delta(&across, &near_edge), Translation::new(-2., -2.));
+		// plain difference when that is shorter
+		assert_close(rect.wrapped_delta(&Position::new(-3., 0.), &Position::new(4., 1.)),
+		             Translation::new(7., 1.));
+	}
+
+	#[test]
+	fn wrapped_delta_is_never_longer_than_half_the_size() {
+		let rect = Rect::new(0., 0., 8., 6.);
+		for i in 0..20 {
+			for j in 0..20 {
+				let from = Position::new(i as f32 * 0.9, j as f32 * 0.7);
+				// kept off the edges, where wrapping may land on either side
+				let to = Position::new(j as f32 * 1.3 + 0.05, i as f32 * 0.4 + 0.05);
+				let d = rect.wrapped_delta(&from, &to);
+				assert!(d.x.abs() <= 4. + 1e-4 && d.y.abs() <= 3. + 1e-4);
+				assert_close(rect.wrap(&(from + d)), rect.wrap(&to));
+			}
+		}
+	}
+}
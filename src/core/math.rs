@@ -199,3 +199,64 @@ impl<T> Inertial<T>
 		self.velocity = self.velocity * T::exp(-dt / self.inertia);
 	}
 }
+
+// pseudo random value in [-1, 1] for each integer lattice point
+fn lattice(i: i32, j: i32, k: i32) -> f32 {
+	let mut h = (i as u32).wrapping_mul(73856093) ^ (j as u32).wrapping_mul(19349663) ^
+	            (k as u32).wrapping_mul(83492791);
+	h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+	h = h ^ (h >> 16);
+	(h & 0xffff) as f32 / 32767.5 - 1.
+}
+
+// smoothly interpolated lattice noise in [-1, 1]
+pub fn value_noise(x: f32, y: f32, z: f32) -> f32 {
+	let (fx, fy, fz) = (x.floor(), y.floor(), z.floor());
+	let (i, j, k) = (fx as i32, fy as i32, fz as i32);
+	let smooth = |t: f32| t * t * (3. - 2. * t);
+	let (u, v, w) = (smooth(x - fx), smooth(y - fy), smooth(z - fz));
+	let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+	let plane = |k: i32| {
+		lerp(lerp(lattice(i, j, k), lattice(i + 1, j, k), u),
+		     lerp(lattice(i, j + 1, k), lattice(i + 1, j + 1, k), u),
+		     v)
+	};
+	lerp(plane(k), plane(k + 1), w)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn noise_hits_the_lattice_at_integer_points() {
+		for &(i, j, k) in &[(0, 0, 0), (3, -2, 7), (-5, 11, -1)] {
+			assert_eq!(value_noise(i as f32, j as f32, k as f32), lattice(i, j, k));
+		}
+	}
+
+	#[test]
+	fn noise_stays_in_range() {
+		for n in 0..1000 {
+			let t = n as f32 * 0.137;
+			let v = value_noise(t, -0.71 * t, 0.29 * t + 3.);
+			assert!(v >= -1. && v <= 1., "{} at {}", v, t);
+		}
+	}
+
+	#[test]
+	fn noise_is_deterministic_and_continuous() {
+		let (x, y, z) = (1.3, -4.2, 0.7);
+		assert_eq!(value_noise(x, y, z), value_noise(x, y, z));
+		let eps = 1e-3;
+		assert!((value_noise(x + eps, y, z) - value_noise(x, y, z)).abs() < 0.01);
+		// no jump when crossing a cell boundary
+		assert!((value_noise(2. - eps, y, z) - value_noise(2., y, z)).abs() < 0.01);
+	}
+
+	#[test]
+	fn noise_varies() {
+		let values = (0..16).map(|i| value_noise(i as f32 + 0.5, 0.5, 0.5)).collect::<Vec<_>>();
+		assert!(values.iter().any(|&v| (v - values[0]).abs() > 0.1));
+	}
+}
@@ -4,3 +4,5 @@ pub mod clock;
 pub mod resource;
 pub mod color;
 pub mod geometry;
+pub mod spatial;
+pub mod config;
@@ -0,0 +1,211 @@
+use std::cmp;
+use std::cmp::Ordering;
+use cgmath::EuclideanVector;
+use core::geometry::*;
+
+// Uniform grid over a rectangular region, rebuilt from scratch every tick.
+// Positions outside the extent are clamped into the border cells.
+// When wrapping, queries see across the edges as on a torus.
+pub struct GridIndex<T: Copy> {
+	extent: Rect,
+	cell_size: f32,
+	wrapping: bool,
+	cols: usize,
+	rows: usize,
+	cells: Vec<Vec<(Position, T)>>,
+}
+
+impl<T: Copy> GridIndex<T> {
+	pub fn new(extent: &Rect, cell_size: f32) -> Self {
+		let cols = cmp::max(((extent.max.x - extent.min.x) / cell_size).ceil() as usize, 1);
+		let rows = cmp::max(((extent.max.y - extent.min.y) / cell_size).ceil() as usize, 1);
+		GridIndex {
+			extent: *extent,
+			cell_size: cell_size,
+			wrapping: false,
+			cols: cols,
+			rows: rows,
+			cells: (0..cols * rows).map(|_| Vec::new()).collect(),
+		}
+	}
+
+	pub fn clear(&mut self) {
+		for cell in self.cells.iter_mut() {
+			cell.clear();
+		}
+	}
+
+	pub fn set_wrapping(&mut self, wrapping: bool) {
+		self.wrapping = wrapping;
+	}
+
+	#[allow(dead_code)]
+	pub fn len(&self) -> usize {
+		self.cells.iter().fold(0, |a, cell| a + cell.len())
+	}
+
+	#[inline]
+	fn col(&self, x: f32) -> usize {
+		let c = ((x - self.extent.min.x) / self.cell_size).floor();
+		if c < 0. { 0 } else { cmp::min(c as usize, self.cols - 1) }
+	}
+
+	#[inline]
+	fn row(&self, y: f32) -> usize {
+		let r = ((y - self.extent.min.y) / self.cell_size).floor();
+		if r < 0. { 0 } else { cmp::min(r as usize, self.rows - 1) }
+	}
+
+	pub fn insert(&mut self, position: Position, item: T) {
+		let index = self.row(position.y) * self.cols + self.col(position.x);
+		self.cells[index].push((position, item));
+	}
+
+	// cells overlapping the span [a, b], going round the edge when wrapping
+	fn span(&self, a: f32, b: f32, min: f32, n: usize) -> Vec<usize> {
+		let (i0, i1) = (((a - min) / self.cell_size).floor() as isize,
+		                ((b - min) / self.cell_size).floor() as isize);
+		if self.wrapping {
+			let n = n as isize;
+			let i1 = cmp::min(i1, i0 + n - 1);
+			(i0..i1 + 1).map(|i| (((i % n) + n) % n) as usize).collect()
+		} else {
+			let clamp = |i: isize| if i < 0 { 0 } else { cmp::min(i as usize, n - 1) };
+			(clamp(i0)..clamp(i1) + 1).collect()
+		}
+	}
+
+	// visits every entry in the cells overlapping the square around p,
+	// callers must still check the actual distance. Entries are seen where they are closest to p
+	fn visit<F>(&self, p: &Position, radius: f32, mut callback: F)
+		where F: FnMut(&Position, T) {
+		let cols = self.span(p.x - radius, p.x + radius, self.extent.min.x, self.cols);
+		let rows = self.span(p.y - radius, p.y + radius, self.extent.min.y, self.rows);
+		for &r in rows.iter() {
+			for &c in cols.iter() {
+				for &(ref position, item) in self.cells[r * self.cols + c].iter() {
+					if self.wrapping {
+						callback(&(*p + self.extent.wrapped_delta(p, position)), item);
+					} else {
+						callback(position, item);
+					}
+				}
+			}
+		}
+	}
+
+	pub fn within(&self, p: &Position, radius: f32) -> Vec<(T, Position)> {
+		let radius2 = radius * radius;
+		let mut found = Vec::new();
+		self.visit(p, radius, |position, item| if (position - p).length2() <= radius2 {
+			found.push((item, *position));
+		});
+		found
+	}
+
+	pub fn nearest_within(&self, p: &Position, radius: f32) -> Option<(T, Position)> {
+		let mut best = radius * radius;
+		let mut found = None;
+		self.visit(p, radius, |position, item| {
+			let d2 = (position - p).length2();
+			if d2 <= best {
+				best = d2;
+				found = Some((item, *position));
+			}
+		});
+		found
+	}
+
+	// up to k entries within the radius, closest first
+	#[allow(dead_code)]
+	pub fn k_nearest(&self, p: &Position, k: usize, radius: f32) -> Vec<(T, Position)> {
+		let radius2 = radius * radius;
+		let mut found = Vec::new();
+		self.visit(p, radius, |position, item| {
+			let d2 = (position - p).length2();
+			if d2 <= radius2 {
+				found.push((d2, item, *position));
+			}
+		});
+		found.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+		found.into_iter().take(k).map(|(_, item, position)| (item, position)).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::geometry::*;
+
+	fn index() -> GridIndex<usize> {
+		let mut index = GridIndex::new(&Rect::new(-10., -10., 10., 10.), 2.);
+		index.insert(Position::new(0., 0.), 0);
+		index.insert(Position::new(1., 1.), 1);
+		index.insert(Position::new(-9.5, 0.), 2);
+		index.insert(Position::new(9.5, 0.), 3);
+		index
+	}
+
+	#[test]
+	fn insert_and_clear() {
+		let mut index = index();
+		assert_eq!(index.len(), 4);
+		index.clear();
+		assert_eq!(index.len(), 0);
+	}
+
+	#[test]
+	fn outside_is_clamped_into_the_border() {
+		let mut index = GridIndex::new(&Rect::new(-10., -10., 10., 10.), 2.);
+		index.insert(Position::new(100., 100.), 7);
+		assert_eq!(index.len(), 1);
+		assert_eq!(index.nearest_within(&Position::new(100., 100.), 1.).map(|(i, _)| i), Some(7));
+	}
+
+	#[test]
+	fn within_checks_the_actual_distance() {
+		let index = index();
+		let mut found = index.within(&Position::new(0., 0.), 1.).iter().map(|&(i, _)| i).collect::<Vec<_>>();
+		found.sort();
+		assert_eq!(found, vec![0]);
+		let mut found = index.within(&Position::new(0., 0.), 1.5).iter().map(|&(i, _)| i).collect::<Vec<_>>();
+		found.sort();
+		assert_eq!(found, vec![0, 1]);
+	}
+
+	#[test]
+	fn nearest_within() {
+		let index = index();
+		assert_eq!(index.nearest_within(&Position::new(0.8, 0.8), 5.).map(|(i, _)| i), Some(1));
+		assert_eq!(index.nearest_within(&Position::new(5., 5.), 1.), None);
+	}
+
+	#[test]
+	fn k_nearest_are_sorted_and_wrap() {
+		let mut index = index();
+		let found = index.k_nearest(&Position::new(0.9, 0.9), 2, 20.).iter().map(|&(i, _)| i).collect::<Vec<_>>();
+		assert_eq!(found, vec![1, 0]);
+		assert_eq!(index.k_nearest(&Position::new(0., 0.), 10, 1.5).len(), 2);
+		assert_eq!(index.k_nearest(&Position::new(-9.5, 0.), 2, 2.).iter().map(|&(i, _)| i).collect::<Vec<_>>(),
+		           vec![2]);
+
+		index.set_wrapping(true);
+		let found = index.k_nearest(&Position::new(-9.5, 0.), 2, 2.);
+		assert_eq!(found.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![2, 3]);
+		assert!((found[1].1.x - -10.5).abs() < 1e-4);
+	}
+
+	#[test]
+	fn wrapping_sees_across_the_edges() {
+		let mut index = index();
+		assert_eq!(index.nearest_within(&Position::new(9.5, 0.), 2.).map(|(i, _)| i), Some(3));
+		assert!(index.within(&Position::new(-9.5, 0.), 2.).iter().all(|&(i, _)| i != 3));
+
+		index.set_wrapping(true);
+		let found = index.within(&Position::new(-9.5, 0.), 2.);
+		let &(_, p) = found.iter().find(|&&(i, _)| i == 3).unwrap();
+		// seen where it is closest, just beyond the left edge
+		assert!((p.x - -10.5).abs() < 1e-4);
+		assert!(p.y.abs() < 1e-4);
+	}
+}